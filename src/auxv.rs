@@ -1,5 +1,9 @@
+use core::fmt;
+
 use zerocopy::{Immutable, IntoBytes};
 
+use crate::{error::ElfParseError, info::ELFParser};
+
 /// Represents the type of an auxiliary vector entry.
 #[derive(Clone, Copy, PartialEq, Eq, IntoBytes, Immutable)]
 #[allow(non_camel_case_types, unused)]
@@ -21,7 +25,7 @@ pub enum AuxType {
     PAGESZ            = 6,
     /// Base address of interpreter
     BASE              = 7,
-    /// Flags
+    /// Flags, unused and always 0 on Linux
     FLAGS             = 8,
     /// Entry point of program
     ENTRY             = 9,
@@ -93,6 +97,69 @@ pub enum AuxType {
     MINSIGSTKSZ       = 51,
 }
 
+impl AuxType {
+    /// The name of the type in the kernel headers, e.g. `"AT_PHDR"`.
+    fn name(self) -> &'static str {
+        match self {
+            AuxType::NULL => "AT_NULL",
+            AuxType::IGNORE => "AT_IGNORE",
+            AuxType::EXECFD => "AT_EXECFD",
+            AuxType::PHDR => "AT_PHDR",
+            AuxType::PHENT => "AT_PHENT",
+            AuxType::PHNUM => "AT_PHNUM",
+            AuxType::PAGESZ => "AT_PAGESZ",
+            AuxType::BASE => "AT_BASE",
+            AuxType::FLAGS => "AT_FLAGS",
+            AuxType::ENTRY => "AT_ENTRY",
+            AuxType::NOTELF => "AT_NOTELF",
+            AuxType::UID => "AT_UID",
+            AuxType::EUID => "AT_EUID",
+            AuxType::GID => "AT_GID",
+            AuxType::EGID => "AT_EGID",
+            AuxType::PLATFORM => "AT_PLATFORM",
+            AuxType::HWCAP => "AT_HWCAP",
+            AuxType::CLKTCK => "AT_CLKTCK",
+            AuxType::FPUCW => "AT_FPUCW",
+            AuxType::DCACHEBSIZE => "AT_DCACHEBSIZE",
+            AuxType::ICACHEBSIZE => "AT_ICACHEBSIZE",
+            AuxType::UCACHEBSIZE => "AT_UCACHEBSIZE",
+            AuxType::IGNOREPPC => "AT_IGNOREPPC",
+            AuxType::SECURE => "AT_SECURE",
+            AuxType::BASE_PLATFORM => "AT_BASE_PLATFORM",
+            AuxType::RANDOM => "AT_RANDOM",
+            AuxType::HWCAP2 => "AT_HWCAP2",
+            AuxType::EXECFN => "AT_EXECFN",
+            AuxType::SYSINFO => "AT_SYSINFO",
+            AuxType::SYSINFO_EHDR => "AT_SYSINFO_EHDR",
+            AuxType::L1I_CACHESHAPE => "AT_L1I_CACHESHAPE",
+            AuxType::L1D_CACHESHAPE => "AT_L1D_CACHESHAPE",
+            AuxType::L2_CACHESHAPE => "AT_L2_CACHESHAPE",
+            AuxType::L3_CACHESHAPE => "AT_L3_CACHESHAPE",
+            AuxType::L1I_CACHESIZE => "AT_L1I_CACHESIZE",
+            AuxType::L1I_CACHEGEOMETRY => "AT_L1I_CACHEGEOMETRY",
+            AuxType::L1D_CACHESIZE => "AT_L1D_CACHESIZE",
+            AuxType::L1D_CACHEGEOMETRY => "AT_L1D_CACHEGEOMETRY",
+            AuxType::L2_CACHESIZE => "AT_L2_CACHESIZE",
+            AuxType::L2_CACHEGEOMETRY => "AT_L2_CACHEGEOMETRY",
+            AuxType::L3_CACHESIZE => "AT_L3_CACHESIZE",
+            AuxType::L3_CACHEGEOMETRY => "AT_L3_CACHEGEOMETRY",
+            AuxType::MINSIGSTKSZ => "AT_MINSIGSTKSZ",
+        }
+    }
+}
+
+impl fmt::Display for AuxType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+impl fmt::Debug for AuxType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Display::fmt(self, f)
+    }
+}
+
 /// Represents an entry in the auxiliary vector.
 #[derive(Clone, Copy, IntoBytes, Immutable)]
 #[repr(C)]
@@ -122,8 +189,248 @@ impl AuxEntry {
         self.auxv_val
     }
 
+    /// Get the `AT_*` number of the type of the auxv entry
+    pub fn key(&self) -> usize {
+        self.auxv_type as usize
+    }
+
+    /// Get the auxv entry as the two words written to the user stack, the
+    /// `AT_*` number and the value
+    pub fn to_pair(&self) -> [usize; 2] {
+        [self.key(), self.auxv_val]
+    }
+
     /// Get a mutable reference to the value of the auxv entry
     pub fn value_mut_ref(&mut self) -> &mut usize {
         &mut self.auxv_val
     }
 }
+
+impl fmt::Debug for AuxEntry {
+    /// Formats the entry like `AT_PHDR => 0x40`.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} => {:#x}", self.auxv_type, self.auxv_val)
+    }
+}
+
+/// Credentials of the process reported in the auxiliary vector.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct Credentials {
+    /// Real user ID, reported as `AT_UID`
+    pub uid: usize,
+    /// Effective user ID, reported as `AT_EUID`
+    pub euid: usize,
+    /// Real group ID, reported as `AT_GID`
+    pub gid: usize,
+    /// Effective group ID, reported as `AT_EGID`
+    pub egid: usize,
+}
+
+impl Credentials {
+    /// Whether the process is privileged (set-uid or set-gid), which is
+    /// reported as `AT_SECURE`.
+    pub fn is_secure(&self) -> bool {
+        self.uid != self.euid || self.gid != self.egid
+    }
+
+    /// The `AT_UID`, `AT_EUID`, `AT_GID`, `AT_EGID` and `AT_SECURE` entries in
+    /// this order.
+    pub fn aux_entries(&self) -> [AuxEntry; 5] {
+        [
+            AuxEntry::new(AuxType::UID, self.uid),
+            AuxEntry::new(AuxType::EUID, self.euid),
+            AuxEntry::new(AuxType::GID, self.gid),
+            AuxEntry::new(AuxType::EGID, self.egid),
+            AuxEntry::new(AuxType::SECURE, self.is_secure() as usize),
+        ]
+    }
+}
+
+/// A builder of the auxiliary vector of a process, started from the
+/// [`ELFParser`] of its executable.
+///
+/// The entries derived from the ELF file are always emitted. Values chosen
+/// by the kernel are set with the methods below, and entries whose values
+/// live in user memory (e.g. [`Self::random`]) must hold the addresses where
+/// the data has already been written. Entries not set are omitted, except
+/// for `AT_PAGESZ` (default 4096), `AT_CLKTCK` (default 100) and the
+/// credentials (default 0).
+#[derive(Clone, Copy)]
+pub struct AuxBuilder<'a> {
+    phdr: Result<usize, ElfParseError>,
+    phent: usize,
+    phnum: usize,
+    entry: usize,
+    pagesz: usize,
+    clktck: usize,
+    interp_base: Option<usize>,
+    vdso_base: Option<usize>,
+    cred: Credentials,
+    hwcap: Option<usize>,
+    hwcap2: Option<usize>,
+    random: Option<usize>,
+    execfn: Option<usize>,
+    platform: Option<usize>,
+    base_platform: Option<usize>,
+    extra: &'a [AuxEntry],
+}
+
+impl<'a> AuxBuilder<'a> {
+    /// Create a builder for the auxiliary vector of `parser`'s executable.
+    pub fn new(parser: &ELFParser<'_>) -> Self {
+        Self {
+            phdr: parser.phdr(),
+            phent: parser.phent(),
+            phnum: parser.phnum(),
+            entry: parser.entry(),
+            pagesz: 4096,
+            clktck: 100,
+            interp_base: None,
+            vdso_base: None,
+            cred: Credentials::default(),
+            hwcap: None,
+            hwcap2: None,
+            random: None,
+            execfn: None,
+            platform: None,
+            base_platform: None,
+            extra: &[],
+        }
+    }
+
+    /// Set `AT_PHDR`, overriding [`ELFParser::phdr`].
+    ///
+    /// This is for ELF files whose program header table is not mapped by any
+    /// segment, so the kernel maps it on its own at `addr`, see
+    /// [`ELFParser::phdr_fallback`].
+    pub fn phdr(mut self, addr: usize) -> Self {
+        self.phdr = Ok(addr);
+        self
+    }
+
+    /// Set `AT_PAGESZ`, the page size of the system.
+    pub fn page_size(mut self, pagesz: usize) -> Self {
+        self.pagesz = pagesz;
+        self
+    }
+
+    /// Set `AT_CLKTCK`, the frequency at which `times()` increments.
+    pub fn clktck(mut self, clktck: usize) -> Self {
+        self.clktck = clktck;
+        self
+    }
+
+    /// Set `AT_BASE`, the load base of the dynamic linker.
+    pub fn interp_base(mut self, base: usize) -> Self {
+        self.interp_base = Some(base);
+        self
+    }
+
+    /// Set `AT_SYSINFO_EHDR`, the address of the ELF header of the vDSO.
+    pub fn vdso_base(mut self, base: usize) -> Self {
+        self.vdso_base = Some(base);
+        self
+    }
+
+    /// Set `AT_UID`, `AT_EUID`, `AT_GID`, `AT_EGID` and `AT_SECURE`.
+    pub fn credentials(mut self, cred: Credentials) -> Self {
+        self.cred = cred;
+        self
+    }
+
+    /// Set `AT_HWCAP`, the arch specific bitmask of CPU capabilities.
+    pub fn hwcap(mut self, hwcap: usize) -> Self {
+        self.hwcap = Some(hwcap);
+        self
+    }
+
+    /// Set `AT_HWCAP2`, the extension of `AT_HWCAP`.
+    pub fn hwcap2(mut self, hwcap2: usize) -> Self {
+        self.hwcap2 = Some(hwcap2);
+        self
+    }
+
+    /// Set `AT_RANDOM`, the address of 16 random bytes.
+    pub fn random(mut self, addr: usize) -> Self {
+        self.random = Some(addr);
+        self
+    }
+
+    /// Set `AT_EXECFN`, the address of the NUL-terminated program path.
+    pub fn execfn(mut self, addr: usize) -> Self {
+        self.execfn = Some(addr);
+        self
+    }
+
+    /// Set `AT_PLATFORM`, the address of the NUL-terminated string
+    /// identifying the CPU (e.g. `"x86_64"`).
+    pub fn platform(mut self, addr: usize) -> Self {
+        self.platform = Some(addr);
+        self
+    }
+
+    /// Set `AT_BASE_PLATFORM`, the address of the NUL-terminated string
+    /// identifying the real platform when it differs from `AT_PLATFORM`
+    /// (e.g. on POWER).
+    pub fn base_platform(mut self, addr: usize) -> Self {
+        self.base_platform = Some(addr);
+        self
+    }
+
+    /// Append entries not covered by the other methods, e.g.
+    /// `AT_MINSIGSTKSZ`.
+    pub fn extra(mut self, extra: &'a [AuxEntry]) -> Self {
+        self.extra = extra;
+        self
+    }
+
+    /// Build the auxiliary vector.
+    ///
+    /// The order is deterministic: `AT_SYSINFO_EHDR`, the entries derived
+    /// from the ELF file (`AT_PHDR`, `AT_PHENT`, `AT_PHNUM`), `AT_PAGESZ`,
+    /// `AT_CLKTCK`, `AT_ENTRY`, `AT_FLAGS` (always 0), `AT_BASE`, the
+    /// credentials, `AT_HWCAP`, `AT_HWCAP2`, `AT_RANDOM`, `AT_EXECFN`,
+    /// `AT_PLATFORM`, `AT_BASE_PLATFORM`, then the [`Self::extra`] entries in
+    /// the given order and the `AT_NULL` terminator. `AT_NULL` entries in
+    /// `extra` are dropped so as not to cut the vector short. Duplicate types
+    /// are kept: consumers see the last entry of each type, so an entry in
+    /// `extra` overrides the others.
+    ///
+    /// Returns [`ElfParseError::PhdrNotMapped`] if `AT_PHDR` can not be
+    /// computed and is not set by [`Self::phdr`], see [`ELFParser::phdr`].
+    pub fn build(self) -> Result<impl Iterator<Item = AuxEntry> + 'a, ElfParseError> {
+        let optional = |at: AuxType, val: Option<usize>| val.map(|val| AuxEntry::new(at, val));
+        Ok(optional(AuxType::SYSINFO_EHDR, self.vdso_base)
+            .into_iter()
+            .chain([
+                AuxEntry::new(AuxType::PHDR, self.phdr?),
+                AuxEntry::new(AuxType::PHENT, self.phent),
+                AuxEntry::new(AuxType::PHNUM, self.phnum),
+                AuxEntry::new(AuxType::PAGESZ, self.pagesz),
+                AuxEntry::new(AuxType::CLKTCK, self.clktck),
+                AuxEntry::new(AuxType::ENTRY, self.entry),
+                AuxEntry::new(AuxType::FLAGS, 0),
+            ])
+            .chain(optional(AuxType::BASE, self.interp_base))
+            .chain(self.cred.aux_entries())
+            .chain(
+                [
+                    optional(AuxType::HWCAP, self.hwcap),
+                    optional(AuxType::HWCAP2, self.hwcap2),
+                    optional(AuxType::RANDOM, self.random),
+                    optional(AuxType::EXECFN, self.execfn),
+                    optional(AuxType::PLATFORM, self.platform),
+                    optional(AuxType::BASE_PLATFORM, self.base_platform),
+                ]
+                .into_iter()
+                .flatten(),
+            )
+            .chain(
+                self.extra
+                    .iter()
+                    .copied()
+                    .filter(|entry| entry.get_type() != AuxType::NULL),
+            )
+            .chain([AuxEntry::new(AuxType::NULL, 0)]))
+    }
+}
@@ -0,0 +1,225 @@
+//! Dynamic section parsed from the `PT_DYNAMIC` segment
+
+use core::ops::Range;
+
+use xmas_elf::header::Class;
+
+use crate::error::ElfParseError;
+
+pub(crate) const DT_NULL: u64 = 0;
+pub(crate) const DT_NEEDED: u64 = 1;
+pub(crate) const DT_PLTRELSZ: u64 = 2;
+pub(crate) const DT_PLTGOT: u64 = 3;
+pub(crate) const DT_HASH: u64 = 4;
+pub(crate) const DT_STRTAB: u64 = 5;
+pub(crate) const DT_SYMTAB: u64 = 6;
+pub(crate) const DT_RELA: u64 = 7;
+pub(crate) const DT_RELASZ: u64 = 8;
+pub(crate) const DT_RELAENT: u64 = 9;
+pub(crate) const DT_STRSZ: u64 = 10;
+pub(crate) const DT_SYMENT: u64 = 11;
+pub(crate) const DT_INIT: u64 = 12;
+pub(crate) const DT_FINI: u64 = 13;
+pub(crate) const DT_SONAME: u64 = 14;
+pub(crate) const DT_RPATH: u64 = 15;
+pub(crate) const DT_REL: u64 = 17;
+pub(crate) const DT_RELSZ: u64 = 18;
+pub(crate) const DT_RELENT: u64 = 19;
+pub(crate) const DT_PLTREL: u64 = 20;
+pub(crate) const DT_JMPREL: u64 = 23;
+pub(crate) const DT_INIT_ARRAY: u64 = 25;
+pub(crate) const DT_FINI_ARRAY: u64 = 26;
+pub(crate) const DT_INIT_ARRAYSZ: u64 = 27;
+pub(crate) const DT_FINI_ARRAYSZ: u64 = 28;
+pub(crate) const DT_RUNPATH: u64 = 29;
+pub(crate) const DT_FLAGS: u64 = 30;
+pub(crate) const DT_PREINIT_ARRAY: u64 = 32;
+pub(crate) const DT_PREINIT_ARRAYSZ: u64 = 33;
+pub(crate) const DT_RELRSZ: u64 = 35;
+pub(crate) const DT_RELR: u64 = 36;
+pub(crate) const DT_RELRENT: u64 = 37;
+pub(crate) const DT_GNU_HASH: u64 = 0x6fff_fef5;
+pub(crate) const DT_FLAGS_1: u64 = 0x6fff_fffb;
+
+/// Common entries of the dynamic section.
+///
+/// Addresses are adjusted by the load base of the ELF file. Entries absent
+/// from the dynamic section are `None` (for addresses) or 0 (for values).
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct DynamicInfo {
+    /// Number of `DT_NEEDED` entries
+    pub needed: usize,
+    /// `DT_STRTAB`: address of the dynamic string table
+    pub strtab: Option<usize>,
+    /// `DT_STRSZ`: size of the dynamic string table
+    pub strsz: u64,
+    /// `DT_SYMTAB`: address of the dynamic symbol table
+    pub symtab: Option<usize>,
+    /// `DT_SYMENT`: size of a symbol table entry
+    pub syment: u64,
+    /// `DT_HASH`: address of the SysV symbol hash table
+    pub hash: Option<usize>,
+    /// `DT_GNU_HASH`: address of the GNU symbol hash table
+    pub gnu_hash: Option<usize>,
+    /// `DT_RELA`: address of the `Rela` relocation table
+    pub rela: Option<usize>,
+    /// `DT_RELASZ`: total size of the `Rela` relocation table
+    pub relasz: u64,
+    /// `DT_RELAENT`: size of a `Rela` relocation entry
+    pub relaent: u64,
+    /// `DT_REL`: address of the `Rel` relocation table
+    pub rel: Option<usize>,
+    /// `DT_RELSZ`: total size of the `Rel` relocation table
+    pub relsz: u64,
+    /// `DT_RELENT`: size of a `Rel` relocation entry
+    pub relent: u64,
+    /// `DT_RELR`: address of the compact relative relocation table
+    pub relr: Option<usize>,
+    /// `DT_RELRSZ`: total size of the `Relr` relocation table
+    pub relrsz: u64,
+    /// `DT_RELRENT`: size of a `Relr` relocation entry
+    pub relrent: u64,
+    /// `DT_JMPREL`: address of the PLT relocations
+    pub jmprel: Option<usize>,
+    /// `DT_PLTRELSZ`: total size of the PLT relocations
+    pub pltrelsz: u64,
+    /// `DT_PLTREL`: type of the PLT relocations (`DT_RELA` or `DT_REL`)
+    pub pltrel: u64,
+    /// `DT_PLTGOT`: address of the PLT and/or GOT
+    pub pltgot: Option<usize>,
+    /// `DT_INIT`: address of the initialization function
+    pub init: Option<usize>,
+    /// `DT_FINI`: address of the termination function
+    pub fini: Option<usize>,
+    /// `DT_INIT_ARRAY`: address of the array of initialization functions
+    pub init_array: Option<usize>,
+    /// `DT_INIT_ARRAYSZ`: size in bytes of `DT_INIT_ARRAY`
+    pub init_arraysz: u64,
+    /// `DT_FINI_ARRAY`: address of the array of termination functions
+    pub fini_array: Option<usize>,
+    /// `DT_FINI_ARRAYSZ`: size in bytes of `DT_FINI_ARRAY`
+    pub fini_arraysz: u64,
+    /// `DT_PREINIT_ARRAY`: address of the array of pre-initialization
+    /// functions
+    pub preinit_array: Option<usize>,
+    /// `DT_PREINIT_ARRAYSZ`: size in bytes of `DT_PREINIT_ARRAY`
+    pub preinit_arraysz: u64,
+    /// `DT_FLAGS`
+    pub flags: u64,
+    /// `DT_FLAGS_1`
+    pub flags_1: u64,
+}
+
+impl DynamicInfo {
+    /// Fill the struct from the `(tag, value)` pairs of the dynamic section.
+    ///
+    /// Returns [`ElfParseError::BadDynamic`] if an address overflows once
+    /// adjusted by `base`.
+    pub(crate) fn parse(
+        entries: impl Iterator<Item = (u64, u64)>,
+        base: usize,
+    ) -> Result<Self, ElfParseError> {
+        let mut info = Self::default();
+        let addr = |val: u64| {
+            usize::try_from(val)
+                .ok()
+                .and_then(|val| val.checked_add(base))
+                .map(Some)
+                .ok_or(ElfParseError::BadDynamic)
+        };
+        for (tag, val) in entries {
+            match tag {
+                DT_NEEDED => info.needed += 1,
+                DT_STRTAB => info.strtab = addr(val)?,
+                DT_STRSZ => info.strsz = val,
+                DT_SYMTAB => info.symtab = addr(val)?,
+                DT_SYMENT => info.syment = val,
+                DT_HASH => info.hash = addr(val)?,
+                DT_GNU_HASH => info.gnu_hash = addr(val)?,
+                DT_RELA => info.rela = addr(val)?,
+                DT_RELASZ => info.relasz = val,
+                DT_RELAENT => info.relaent = val,
+                DT_REL => info.rel = addr(val)?,
+                DT_RELSZ => info.relsz = val,
+                DT_RELENT => info.relent = val,
+                DT_RELR => info.relr = addr(val)?,
+                DT_RELRSZ => info.relrsz = val,
+                DT_RELRENT => info.relrent = val,
+                DT_JMPREL => info.jmprel = addr(val)?,
+                DT_PLTRELSZ => info.pltrelsz = val,
+                DT_PLTREL => info.pltrel = val,
+                DT_PLTGOT => info.pltgot = addr(val)?,
+                DT_INIT => info.init = addr(val)?,
+                DT_FINI => info.fini = addr(val)?,
+                DT_INIT_ARRAY => info.init_array = addr(val)?,
+                DT_INIT_ARRAYSZ => info.init_arraysz = val,
+                DT_FINI_ARRAY => info.fini_array = addr(val)?,
+                DT_FINI_ARRAYSZ => info.fini_arraysz = val,
+                DT_PREINIT_ARRAY => info.preinit_array = addr(val)?,
+                DT_PREINIT_ARRAYSZ => info.preinit_arraysz = val,
+                DT_FLAGS => info.flags = val,
+                DT_FLAGS_1 => info.flags_1 = val,
+                _ => {}
+            }
+        }
+        Ok(info)
+    }
+
+    /// The address range of the `DT_PREINIT_ARRAY` function pointers.
+    ///
+    /// Only executables have this array. Its functions are run first, before
+    /// those of [`Self::init_array`] of any loaded object. Returns `None` if
+    /// the tag is absent or if the end of the array overflows.
+    pub fn preinit_array(&self) -> Option<Range<usize>> {
+        array_range(self.preinit_array, self.preinit_arraysz)
+    }
+
+    /// The address range of the `DT_INIT_ARRAY` function pointers.
+    ///
+    /// Its functions are run in order at startup, after `DT_INIT`. `None` is
+    /// returned as in [`Self::preinit_array`].
+    pub fn init_array(&self) -> Option<Range<usize>> {
+        array_range(self.init_array, self.init_arraysz)
+    }
+
+    /// The address range of the `DT_FINI_ARRAY` function pointers.
+    ///
+    /// Its functions are run in reverse order at exit, before `DT_FINI`.
+    /// `None` is returned as in [`Self::preinit_array`].
+    pub fn fini_array(&self) -> Option<Range<usize>> {
+        array_range(self.fini_array, self.fini_arraysz)
+    }
+}
+
+/// The range of `size` bytes at `addr`, or `None` if it is absent or its end
+/// overflows.
+fn array_range(addr: Option<usize>, size: u64) -> Option<Range<usize>> {
+    let addr = addr?;
+    Some(addr..addr.checked_add(usize::try_from(size).ok()?)?)
+}
+
+/// Iterate the `(tag, value)` pairs of the dynamic section up to `DT_NULL`.
+///
+/// Returns [`ElfParseError::BadDynamic`] if the section is not terminated by
+/// `DT_NULL`.
+pub(crate) fn dynamic_entries(
+    data: &[u8],
+    class: Class,
+) -> Result<impl Iterator<Item = (u64, u64)> + Clone + '_, ElfParseError> {
+    let word = match class {
+        Class::ThirtyTwo => 4,
+        _ => 8,
+    };
+    let read = move |bytes: &[u8]| match word {
+        4 => u32::from_ne_bytes(bytes.try_into().unwrap()) as u64,
+        _ => u64::from_ne_bytes(bytes.try_into().unwrap()),
+    };
+    let entries = data
+        .chunks_exact(word * 2)
+        .map(move |entry| (read(&entry[..word]), read(&entry[word..])));
+    let mut check = entries.clone();
+    if !check.any(|(tag, _)| tag == DT_NULL) {
+        return Err(ElfParseError::BadDynamic);
+    }
+    Ok(entries.take_while(|&(tag, _)| tag != DT_NULL))
+}
@@ -0,0 +1,83 @@
+//! Errors reported when parsing an ELF file
+
+use core::fmt;
+
+/// Errors that can occur when parsing an ELF file.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ElfParseError {
+    /// The file does not start with the ELF magic number.
+    BadMagic,
+    /// The ELF class is neither 32-bit nor 64-bit.
+    UnsupportedClass,
+    /// The data encoding of the ELF file doesn't match the host byte order.
+    WrongEndian,
+    /// The ELF type is not supported.
+    UnsupportedType,
+    /// The program header table is not mapped by any `LOAD` segment.
+    PhdrNotMapped,
+    /// The data is shorter than what the headers declare.
+    Truncated,
+    /// The dynamic section or a table it refers to is malformed.
+    BadDynamic,
+    /// The operation is not supported for the machine of the ELF file.
+    UnsupportedArch,
+    /// The ELF file targets another machine than expected.
+    WrongArch,
+    /// The ELF version is not `EV_CURRENT`.
+    UnsupportedVersion,
+    /// Two `LOAD` segments overlap in the virtual memory.
+    OverlappingSegments,
+    /// An address overflows once adjusted by the load base.
+    AddressOverflow,
+    /// The ELF file has no `LOAD` segment to map.
+    NoLoadableSegments,
+    /// `e_phentsize` is not the size of a program header of the ELF class.
+    BadPhentSize,
+    /// The entry point is not in an executable `LOAD` segment.
+    BadEntry,
+    /// The offset and the virtual address of a `LOAD` segment are not
+    /// congruent modulo its alignment.
+    MisalignedSegment,
+    /// The path in the `PT_INTERP` segment is not a NUL-terminated UTF-8
+    /// string.
+    BadInterp,
+    /// A `LOAD` segment starts below the lowest address allowed to map.
+    MapsBelowMin,
+    /// The ELF file targets another OS ABI than System V or Linux.
+    UnsupportedOsAbi,
+    /// The program header table has more than [`MAX_PHNUM`] entries, which
+    /// is the limit without the `alloc` feature.
+    ///
+    /// [`MAX_PHNUM`]: crate::MAX_PHNUM
+    TooManyHeaders,
+}
+
+impl fmt::Display for ElfParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let msg = match self {
+            ElfParseError::BadMagic => "did not find ELF magic number",
+            ElfParseError::UnsupportedClass => "unsupported ELF class",
+            ElfParseError::WrongEndian => "ELF data encoding doesn't match the host",
+            ElfParseError::UnsupportedType => "unsupported ELF type",
+            ElfParseError::PhdrNotMapped => "program header table is not mapped",
+            ElfParseError::Truncated => "ELF file is truncated",
+            ElfParseError::BadDynamic => "malformed dynamic section",
+            ElfParseError::UnsupportedArch => "unsupported machine",
+            ElfParseError::WrongArch => "ELF file targets another machine",
+            ElfParseError::UnsupportedVersion => "unsupported ELF version",
+            ElfParseError::OverlappingSegments => "LOAD segments overlap",
+            ElfParseError::AddressOverflow => "address overflows",
+            ElfParseError::NoLoadableSegments => "no LOAD segment",
+            ElfParseError::BadPhentSize => "bad program header entry size",
+            ElfParseError::BadEntry => "entry point is not in an executable segment",
+            ElfParseError::MisalignedSegment => "segment offset and address are misaligned",
+            ElfParseError::BadInterp => "malformed interpreter path",
+            ElfParseError::MapsBelowMin => "segment maps below the minimum address",
+            ElfParseError::UnsupportedOsAbi => "unsupported OS ABI",
+            ElfParseError::TooManyHeaders => "too many program headers",
+        };
+        f.write_str(msg)
+    }
+}
+
+impl core::error::Error for ElfParseError {}
@@ -1,32 +1,124 @@
 //! ELF information parsed from the ELF file
 
+#[cfg(feature = "alloc")]
 use alloc::vec::Vec;
-use core::ops::Range;
+use core::{
+    fmt,
+    ops::{Deref, Range},
+};
 
 use xmas_elf::{
-    header::Class,
-    program::{ProgramHeader32, ProgramHeader64},
+    header::{Class, Data, Machine, OsAbi, Version},
+    program::{ProgramHeader32, ProgramHeader64, Type},
+};
+
+use crate::{
+    auxv::{AuxBuilder, AuxEntry, Credentials},
+    dynamic::{DT_NEEDED, DT_RELA, DT_RPATH, DT_RUNPATH, DT_SONAME, DynamicInfo, dynamic_entries},
+    error::ElfParseError,
+    note::{
+        ELF_NOTE_OS_LINUX, GnuProperties, NT_GNU_ABI_TAG, NT_GNU_BUILD_ID, NT_GNU_PROPERTY_TYPE_0,
+        Note, note_entries,
+    },
+    read::ReadAt,
+    reloc::{RelocKind, Relocation, rela_entries, reloc_kinds_for, relr_entries},
+    section::{RawSection, SHT_DYNSYM, SHT_SYMTAB, SectionInfo, section_headers, string_at},
+    segment::{ELFPH, MapRegion},
+    symbol::{SHN_ABS, SHN_UNDEF, symbol_entries},
 };
 
-use crate::auxv::{AuxEntry, AuxType};
+/// The maximum number of program headers of an ELF file without the `alloc`
+/// feature.
+///
+/// The program headers are then stored inline in [`ELFHeaders`], and tables
+/// with more entries are rejected with [`ElfParseError::TooManyHeaders`].
+/// Linux binaries usually have around a dozen of them.
+pub const MAX_PHNUM: usize = 32;
+
+/// The segment type describing the stack permissions.
+const PT_GNU_EH_FRAME: u32 = 0x6474_e550;
+const PT_GNU_STACK: u32 = 0x6474_e551;
+const PT_GNU_PROPERTY: u32 = 0x6474_e553;
+
+/// Translate the link-time virtual address range `[vaddr, vaddr + len)` to
+/// its file offset, if it is backed by the file in a `LOAD` segment of `phs`.
+fn file_offset_in(phs: &[ProgramHeader64], vaddr: u64, len: u64) -> Option<usize> {
+    let end = vaddr.checked_add(len)?;
+    phs.iter()
+        .filter(|ph| ph.get_type() == Ok(Type::Load))
+        .find(|ph| ph.virtual_addr <= vaddr && end <= ph.virtual_addr.saturating_add(ph.file_size))
+        .and_then(|ph| (vaddr - ph.virtual_addr).checked_add(ph.offset))
+        .and_then(|offset| usize::try_from(offset).ok())
+}
+
+/// The raw `p_type` of a program header.
+///
+/// Types xmas_elf rejects as invalid are reported as `PT_NULL`, i.e. unused.
+fn raw_type(ph: &ProgramHeader64) -> u32 {
+    match ph.get_type() {
+        Ok(Type::Null) | Err(_) => 0,
+        Ok(Type::Load) => 1,
+        Ok(Type::Dynamic) => 2,
+        Ok(Type::Interp) => 3,
+        Ok(Type::Note) => 4,
+        Ok(Type::ShLib) => 5,
+        Ok(Type::Phdr) => 6,
+        Ok(Type::Tls) => 7,
+        Ok(Type::GnuRelro) => 0x6474_e552,
+        Ok(Type::OsSpecific(t) | Type::ProcessorSpecific(t)) => t,
+    }
+}
 
 pub struct ELFHeadersBuilder<'a>(ELFHeaders<'a>);
 impl<'a> ELFHeadersBuilder<'a> {
-    pub fn new(input: &'a [u8]) -> Result<Self, &'static str> {
+    pub fn new(input: &'a [u8]) -> Result<Self, ElfParseError> {
+        if input.len() < 16 {
+            return Err(ElfParseError::Truncated);
+        }
+        if input[..4] != xmas_elf::header::MAGIC {
+            return Err(ElfParseError::BadMagic);
+        }
+        if !matches!(input[4], 1 | 2) {
+            return Err(ElfParseError::UnsupportedClass);
+        }
+        // The fields are read in the host byte order.
+        let host_data = if cfg!(target_endian = "little") { 1 } else { 2 };
+        if input[5] != host_data {
+            return Err(ElfParseError::WrongEndian);
+        }
         Ok(Self(ELFHeaders {
-            header: xmas_elf::header::parse_header(input)?,
-            ph: Vec::new(),
+            header: xmas_elf::header::parse_header(input).map_err(|_| ElfParseError::Truncated)?,
+            ph: ProgramHeaders::default(),
         }))
     }
 
-    pub fn ph_range(&self) -> Range<u64> {
+    /// The range of file offsets of the program header table, to be read and
+    /// passed to [`Self::build`].
+    ///
+    /// Returns [`ElfParseError::Truncated`] if the end of the table overflows,
+    /// as it can't be in the file.
+    pub fn ph_range(&self) -> Result<Range<u64>, ElfParseError> {
         let start = self.0.header.pt2.ph_offset();
         let size = self.0.header.pt2.ph_entry_size() as u64 * self.0.header.pt2.ph_count() as u64;
-        start..start + size
+        let end = start.checked_add(size).ok_or(ElfParseError::Truncated)?;
+        Ok(start..end)
     }
 
-    pub fn build(mut self, ph: &[u8]) -> Result<ELFHeaders<'a>, &'static str> {
-        self.0.ph = ph
+    pub fn build(mut self, ph: &[u8]) -> Result<ELFHeaders<'a>, ElfParseError> {
+        let entsize = self.0.header.pt2.ph_entry_size() as usize;
+        let min_entsize = match self.0.header.pt1.class() {
+            Class::ThirtyTwo => size_of::<ProgramHeader32>(),
+            _ => size_of::<ProgramHeader64>(),
+        };
+        let count = self.0.header.pt2.ph_count() as usize;
+        if count == 0 {
+            // e.g. relocatable objects, whose `e_phentsize` may be 0
+            return Ok(self.0);
+        }
+        if entsize < min_entsize || ph.len() < entsize * count {
+            return Err(ElfParseError::Truncated);
+        }
+        let headers = ph[..entsize * count]
             .chunks_exact(self.0.header.pt2.ph_entry_size() as usize)
             .map(|chunk| match self.0.header.pt1.class() {
                 Class::ThirtyTwo => {
@@ -44,63 +136,523 @@ impl<'a> ELFHeadersBuilder<'a> {
                 }
                 Class::SixtyFour => *zero::read(chunk),
                 Class::None | Class::Other(_) => unreachable!(),
-            })
-            .collect();
+            });
+        self.0.ph = ProgramHeaders::new(headers)?;
         Ok(self.0)
     }
 }
 
+/// The program headers of an ELF file, widened to the 64-bit layout for
+/// ELF32 files.
+///
+/// They are stored in a `Vec` with the `alloc` feature, and inline without
+/// it, in which case at most [`MAX_PHNUM`] of them are supported. Either way
+/// they are read as a slice.
+#[derive(Clone, Default)]
+pub struct ProgramHeaders {
+    #[cfg(feature = "alloc")]
+    headers: Vec<ProgramHeader64>,
+    #[cfg(not(feature = "alloc"))]
+    headers: [ProgramHeader64; MAX_PHNUM],
+    #[cfg(not(feature = "alloc"))]
+    len: usize,
+}
+
+impl ProgramHeaders {
+    /// Store the program headers yielded by `headers`.
+    ///
+    /// Returns [`ElfParseError::TooManyHeaders`] if there are more than
+    /// [`MAX_PHNUM`] of them without the `alloc` feature.
+    fn new(headers: impl Iterator<Item = ProgramHeader64>) -> Result<Self, ElfParseError> {
+        let mut table = Self::default();
+        for ph in headers {
+            #[cfg(feature = "alloc")]
+            table.headers.push(ph);
+            #[cfg(not(feature = "alloc"))]
+            {
+                *table
+                    .headers
+                    .get_mut(table.len)
+                    .ok_or(ElfParseError::TooManyHeaders)? = ph;
+                table.len += 1;
+            }
+        }
+        Ok(table)
+    }
+}
+
+impl Deref for ProgramHeaders {
+    type Target = [ProgramHeader64];
+
+    fn deref(&self) -> &Self::Target {
+        #[cfg(feature = "alloc")]
+        return &self.headers;
+        #[cfg(not(feature = "alloc"))]
+        return &self.headers[..self.len];
+    }
+}
+
+/// The initial thread-local storage image described by the `PT_TLS` segment.
+///
+/// The first `filesz` bytes at `vaddr` are the `.tdata` template which must be
+/// copied into every new thread's TLS block. The remaining `memsz - filesz`
+/// bytes are the `.tbss` region, which has no file backing and must be
+/// zero-filled by the loader.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TlsInfo {
+    /// The virtual address of the TLS template, adjusted by the load base.
+    pub vaddr: usize,
+    /// Size of the initialized `.tdata` part of the template.
+    pub filesz: u64,
+    /// Total size of the TLS block, including the zero-filled `.tbss` part.
+    pub memsz: u64,
+    /// Required alignment of the TLS block.
+    pub align: u64,
+}
+
+/// How to zero the BSS of a segment, see [`ELFParser::bss_zero_plan`]: the
+/// partial page as `(addr, len)`, and the range of the fresh zero pages.
+pub type BssZeroPlan = (Option<(usize, usize)>, Range<usize>);
+
+/// What a loader needs to map and start an ELF file, see
+/// [`ELFParser::plan`].
+#[cfg(feature = "alloc")]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LoadPlan<'d> {
+    /// The entry point, adjusted by the load base. For a program with an
+    /// interpreter, execution starts at the entry point of the interpreter
+    /// instead, and this is passed to it as `AT_ENTRY`.
+    pub entry: usize,
+    /// The page-aligned range spanned by the `LOAD` segments, including the
+    /// holes between them, to be reserved before mapping the regions.
+    pub image: Range<usize>,
+    /// The page-aligned mappings of the `LOAD` segments, sorted by address,
+    /// see [`ELFParser::merged_load_regions`]. The BSS of each segment must
+    /// still be zeroed after its file contents, see
+    /// [`ELFParser::bss_zero_plan`].
+    pub regions: Vec<MapRegion>,
+    /// The path of the interpreter to load, from `PT_INTERP`, or `None` if
+    /// the ELF file is not to be run through an interpreter.
+    pub interp: Option<&'d str>,
+    /// The range to make read-only once the relocations are applied, from
+    /// `PT_GNU_RELRO`, see [`ELFParser::relro`].
+    pub relro: Option<Range<usize>>,
+    /// Whether the stack must be executable, see
+    /// [`ELFParser::executable_stack`].
+    pub executable_stack: bool,
+}
+
+/// The ELF header and the program header table, read once by
+/// [`ELFHeadersBuilder`].
+///
+/// The program headers are decoded into `ph` when the headers are built, and
+/// every [`ELFParser`] method reads them from there, so the table is never
+/// parsed again, however many accessors are called.
 pub struct ELFHeaders<'a> {
     pub header: xmas_elf::header::Header<'a>,
-    pub ph: Vec<ProgramHeader64>,
+    /// The program headers, widened to the 64-bit layout for ELF32 files.
+    pub ph: ProgramHeaders,
+}
+
+impl<'a> ELFHeaders<'a> {
+    /// Read the headers from `data` in one go.
+    ///
+    /// `data` is the content of the ELF file, at least up to the end of the
+    /// program header table. This is a shorthand for [`ELFHeadersBuilder`]
+    /// when the whole file is in memory, and the result is then passed to
+    /// [`ELFParser::new`].
+    ///
+    /// Returns [`ElfParseError::Truncated`] if the program header table is
+    /// out of the bounds of `data`.
+    pub fn from_bytes(data: &'a [u8]) -> Result<Self, ElfParseError> {
+        let builder = ELFHeadersBuilder::new(data)?;
+        let range = builder.ph_range()?;
+        let ph = usize::try_from(range.start)
+            .ok()
+            .zip(usize::try_from(range.end).ok())
+            .and_then(|(start, end)| data.get(start..end))
+            .ok_or(ElfParseError::Truncated)?;
+        builder.build(ph)
+    }
 }
 
 /// A wrapper for the ELF file data with some useful methods.
+///
+/// Both ELF32 and ELF64 files are supported: 32-bit program headers are
+/// widened when the headers are built, so all addresses and sizes are
+/// reported the same way.
 pub struct ELFParser<'a> {
     headers: &'a ELFHeaders<'a>,
     /// Base address of the ELF file loaded into the memory.
     base: usize,
 }
 
+impl fmt::Debug for ELFParser<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ELFParser")
+            .field("base", &format_args!("{:#x}", self.base))
+            .field("entry", &format_args!("{:#x}", self.entry()))
+            .field("phnum", &self.phnum())
+            .field("is_pie", &self.is_pie())
+            .finish()
+    }
+}
+
 impl<'a> ELFParser<'a> {
     /// Create a new `ELFInfo` instance.
-    pub fn new(headers: &'a ELFHeaders<'a>, bias: usize) -> Result<Self, &'static str> {
-        let base = if headers.header.pt2.type_().as_type() == xmas_elf::header::Type::SharedObject {
-            bias
-        } else {
-            0
+    ///
+    /// `bias` is the load bias of a shared object: the difference between
+    /// the runtime and link-time addresses, added to every `p_vaddr`. It is
+    /// not the address of the first segment, unless that segment is linked
+    /// at 0. Executables are loaded at their link-time addresses, see
+    /// [`Self::new_with_policy`] to bias them too.
+    ///
+    /// Returns [`ElfParseError::UnsupportedType`] if the ELF file is neither
+    /// an executable nor a shared object,
+    /// [`ElfParseError::UnsupportedVersion`] if `e_ident[EI_VERSION]` or
+    /// `e_version` is not `EV_CURRENT`, and
+    /// [`ElfParseError::AddressOverflow`] if the entry point or the end of a
+    /// segment overflows once adjusted by `bias`. The addresses reported by
+    /// the other methods thus never wrap around, though rounding them up to a
+    /// page may, see [`Self::image_range`].
+    pub fn new(headers: &'a ELFHeaders<'a>, bias: usize) -> Result<Self, ElfParseError> {
+        Self::new_with_policy(headers, bias, false)
+    }
+
+    /// Create a new `ELFInfo` instance, choosing whether `bias` also applies
+    /// to executables.
+    ///
+    /// With `honor_bias_for_exec` false, this is [`Self::new`]: `bias` is
+    /// silently ignored for executables, whose [`Self::base`] is 0. With it
+    /// true, executables are biased like shared objects, e.g. to run
+    /// position-dependent code at another address in a sandbox that
+    /// translates the addresses. Compare [`Self::base`] with `bias` to tell
+    /// whether it was applied. Errors are reported as in [`Self::new`].
+    pub fn new_with_policy(
+        headers: &'a ELFHeaders<'a>,
+        bias: usize,
+        honor_bias_for_exec: bool,
+    ) -> Result<Self, ElfParseError> {
+        let base = match headers.header.pt2.type_().as_type() {
+            xmas_elf::header::Type::SharedObject => bias,
+            xmas_elf::header::Type::Executable if honor_bias_for_exec => bias,
+            xmas_elf::header::Type::Executable => 0,
+            _ => return Err(ElfParseError::UnsupportedType),
+        };
+        if headers.header.pt1.version() != Version::Current || headers.header.pt2.version() != 1 {
+            return Err(ElfParseError::UnsupportedVersion);
+        }
+        let biased = |addr: Option<u64>| {
+            addr.and_then(|addr| usize::try_from(addr).ok())
+                .and_then(|addr| addr.checked_add(base))
         };
+        if biased(Some(headers.header.pt2.entry_point())).is_none()
+            || headers
+                .ph
+                .iter()
+                .any(|ph| biased(ph.virtual_addr.checked_add(ph.mem_size)).is_none())
+        {
+            return Err(ElfParseError::AddressOverflow);
+        }
         Ok(Self { headers, base })
     }
 
+    /// Create a new `ELFInfo` instance, checking that the ELF file targets the
+    /// `expected` machine.
+    ///
+    /// Returns [`ElfParseError::WrongArch`] if `e_machine` doesn't match.
+    pub fn new_for(
+        headers: &'a ELFHeaders<'a>,
+        bias: usize,
+        expected: Machine,
+    ) -> Result<Self, ElfParseError> {
+        let parser = Self::new(headers, bias)?;
+        if parser.machine() != expected {
+            return Err(ElfParseError::WrongArch);
+        }
+        Ok(parser)
+    }
+
+    /// Validate the ELF header in a single call, returning the first failure.
+    ///
+    /// The following are checked, in order:
+    ///
+    /// * the magic number, else [`ElfParseError::BadMagic`]
+    /// * the class, else [`ElfParseError::UnsupportedClass`]
+    /// * the data encoding, else [`ElfParseError::WrongEndian`]
+    /// * the type, else [`ElfParseError::UnsupportedType`]
+    /// * the machine, if `expected_machine` is given, else
+    ///   [`ElfParseError::WrongArch`]
+    /// * both `e_ident[EI_VERSION]` and `e_version`, else
+    ///   [`ElfParseError::UnsupportedVersion`]
+    /// * `e_phentsize`, which must be the size of a program header of the
+    ///   class, else [`ElfParseError::BadPhentSize`]
+    /// * at least one `LOAD` segment, else
+    ///   [`ElfParseError::NoLoadableSegments`]
+    ///
+    /// The first four and the version always hold for headers read by
+    /// [`ELFHeadersBuilder`] and accepted by [`ELFParser::new`], but are
+    /// checked again so that this is the single point where a loader decides
+    /// to return `ENOEXEC`.
+    pub fn validate(&self, expected_machine: Option<Machine>) -> Result<(), ElfParseError> {
+        let header = &self.headers.header;
+        if header.pt1.magic != xmas_elf::header::MAGIC {
+            return Err(ElfParseError::BadMagic);
+        }
+        if !matches!(header.pt1.class(), Class::ThirtyTwo | Class::SixtyFour) {
+            return Err(ElfParseError::UnsupportedClass);
+        }
+        let host_data = if cfg!(target_endian = "little") {
+            Data::LittleEndian
+        } else {
+            Data::BigEndian
+        };
+        if header.pt1.data() != host_data {
+            return Err(ElfParseError::WrongEndian);
+        }
+        if !matches!(
+            header.pt2.type_().as_type(),
+            xmas_elf::header::Type::Executable | xmas_elf::header::Type::SharedObject
+        ) {
+            return Err(ElfParseError::UnsupportedType);
+        }
+        if expected_machine.is_some_and(|machine| machine != self.machine()) {
+            return Err(ElfParseError::WrongArch);
+        }
+        if header.pt1.version() != Version::Current || header.pt2.version() != 1 {
+            return Err(ElfParseError::UnsupportedVersion);
+        }
+        let phent = match header.pt1.class() {
+            Class::ThirtyTwo => size_of::<ProgramHeader32>(),
+            _ => size_of::<ProgramHeader64>(),
+        };
+        if self.phnum() != 0 && self.phent() != phent {
+            return Err(ElfParseError::BadPhentSize);
+        }
+        if self.ph_load().next().is_none() {
+            return Err(ElfParseError::NoLoadableSegments);
+        }
+        Ok(())
+    }
+
+    /// The version of the ELF file (`e_version`), always 1 (`EV_CURRENT`).
+    pub fn version(&self) -> u32 {
+        self.headers.header.pt2.version()
+    }
+
+    /// The raw `EI_OSABI` byte of the ELF file, e.g. 0 for `ELFOSABI_SYSV`
+    /// and 3 for `ELFOSABI_LINUX`.
+    ///
+    /// Most Linux binaries have 0, those using GNU extensions such as IFUNCs
+    /// may have 3. See [`Self::validate_os_abi`] to accept only these.
+    pub fn os_abi(&self) -> u8 {
+        match self.headers.header.pt1.os_abi() {
+            OsAbi::SystemV => 0x00,
+            OsAbi::HpUx => 0x01,
+            OsAbi::NetBSD => 0x02,
+            OsAbi::Linux => 0x03,
+            OsAbi::Solaris => 0x06,
+            OsAbi::Aix => 0x07,
+            OsAbi::Irix => 0x08,
+            OsAbi::FreeBSD => 0x09,
+            OsAbi::OpenBSD => 0x0c,
+            OsAbi::OpenVMS => 0x0d,
+            OsAbi::Other(abi) => abi,
+        }
+    }
+
+    /// Check that the ELF file is meant for Linux, i.e. that its
+    /// [`Self::os_abi`] is `ELFOSABI_SYSV` (0) or `ELFOSABI_LINUX` (3).
+    ///
+    /// Returns [`ElfParseError::UnsupportedOsAbi`] otherwise. This is not
+    /// part of [`Self::validate`], as kernels differ in which ABIs they run.
+    pub fn validate_os_abi(&self) -> Result<(), ElfParseError> {
+        if !matches!(self.os_abi(), 0 | 3) {
+            return Err(ElfParseError::UnsupportedOsAbi);
+        }
+        Ok(())
+    }
+
+    /// The target machine of the ELF file.
+    pub fn machine(&self) -> Machine {
+        self.headers.header.pt2.machine().as_machine()
+    }
+
+    /// Whether the ELF file is a position-independent executable: a shared
+    /// object with an entry point.
+    ///
+    /// Note that this also holds for the dynamic linkers, which can be run as
+    /// programs.
+    pub fn is_pie(&self) -> bool {
+        self.headers.header.pt2.type_().as_type() == xmas_elf::header::Type::SharedObject
+            && self.headers.header.pt2.entry_point() != 0
+    }
+
+    /// Whether the ELF file has a `PT_DYNAMIC` or a `PT_INTERP` segment.
+    ///
+    /// This includes static PIEs, which have a dynamic section to relocate
+    /// themselves but no interpreter.
+    pub fn is_dynamic(&self) -> bool {
+        self.headers
+            .ph
+            .iter()
+            .any(|ph| matches!(ph.get_type(), Ok(Type::Dynamic | Type::Interp)))
+    }
+
+    /// Whether the ELF file is a classic static binary, with neither a
+    /// `PT_DYNAMIC` nor a `PT_INTERP` segment.
+    pub fn is_static(&self) -> bool {
+        !self.is_dynamic()
+    }
+
+    /// Whether the ELF file is a static PIE: a [PIE](Self::is_pie) with a
+    /// `PT_DYNAMIC` segment holding its relocations but no `PT_INTERP`
+    /// segment.
+    ///
+    /// No interpreter relocates such a program, so the loader applies its
+    /// relocations itself, see [`Self::apply_relative`]. For a PIE with an
+    /// interpreter this is left to the interpreter. Like [`Self::is_pie`],
+    /// this also holds for the dynamic linkers.
+    pub fn is_static_pie(&self) -> bool {
+        self.is_pie()
+            && self
+                .headers
+                .ph
+                .iter()
+                .any(|ph| ph.get_type() == Ok(Type::Dynamic))
+            && !self.needs_interp()
+    }
+
+    /// Whether the ELF file has a `PT_INTERP` segment, i.e. whether an
+    /// interpreter is to be loaded and its base passed as `ldso_base` to
+    /// [`Self::aux_vector`].
+    pub fn needs_interp(&self) -> bool {
+        self.headers
+            .ph
+            .iter()
+            .any(|ph| ph.get_type() == Ok(Type::Interp))
+    }
+
+    /// The path of the interpreter from the `PT_INTERP` segment, e.g.
+    /// `"/lib64/ld-linux-x86-64.so.2"`.
+    ///
+    /// `data` is the content of the whole ELF file. Returns `Ok(None)` if
+    /// there is no `PT_INTERP` segment, [`ElfParseError::Truncated`] if it is
+    /// out of the bounds of `data`, and [`ElfParseError::BadInterp`] if it
+    /// is not a NUL-terminated UTF-8 string.
+    pub fn interp<'d>(&self, data: &'d [u8]) -> Result<Option<&'d str>, ElfParseError> {
+        let Some(ph) = self
+            .headers
+            .ph
+            .iter()
+            .find(|ph| ph.get_type() == Ok(Type::Interp))
+        else {
+            return Ok(None);
+        };
+        let path = (ph.offset as usize)
+            .checked_add(ph.file_size as usize)
+            .and_then(|end| data.get(ph.offset as usize..end))
+            .ok_or(ElfParseError::Truncated)?;
+        path.strip_suffix(b"\0")
+            .and_then(|path| core::str::from_utf8(path).ok())
+            .map(Some)
+            .ok_or(ElfParseError::BadInterp)
+    }
+
     /// The entry point of the ELF file.
+    ///
+    /// It includes the base address the parser was created with, so for an
+    /// interpreter parsed with its load base as bias, this is already the
+    /// initial PC to jump to. As `base` is a bias also applied to the
+    /// segments, no adjustment by the lowest `p_vaddr` is needed.
     pub fn entry(&self) -> usize {
-        // TODO: base_load_address_offset?
         self.headers.header.pt2.entry_point() as usize + self.base
     }
 
+    /// The entry point of the ELF file loaded at `base` instead of the base
+    /// address of the parser.
+    ///
+    /// This is for callers that parsed e.g. the interpreter with bias 0 and
+    /// chose its load base afterwards. Returns `None` if the entry point
+    /// overflows once adjusted by `base`.
+    pub fn entry_with_base(&self, base: usize) -> Option<usize> {
+        (self.headers.header.pt2.entry_point() as usize).checked_add(base)
+    }
+
     /// The number of program headers in the ELF file.
     pub fn phnum(&self) -> usize {
         self.headers.header.pt2.ph_count() as usize
     }
 
+    /// The number of `LOAD` segments in the ELF file, e.g. to size a fixed
+    /// array of mappings.
+    pub fn load_count(&self) -> usize {
+        self.headers
+            .ph
+            .iter()
+            .filter(|ph| ph.get_type() == Ok(Type::Load))
+            .count()
+    }
+
     /// The size of the program header table entry in the ELF file.
     pub fn phent(&self) -> usize {
         self.headers.header.pt2.ph_entry_size() as usize
     }
 
-    /// The offset of the program header table in the ELF file.
-    pub fn phdr(&self) -> usize {
-        let ph_offset = self.headers.header.pt2.ph_offset() as usize;
+    /// The virtual address of the program header table in the memory.
+    ///
+    /// The address is taken from the `PT_PHDR` segment if present. Otherwise
+    /// it is computed from the `LOAD` segment containing the program header
+    /// table, and [`ElfParseError::PhdrNotMapped`] is returned if there is no
+    /// such segment, or [`ElfParseError::AddressOverflow`] if the address
+    /// overflows, as the table may lie past the `p_memsz` of the segment.
+    pub fn phdr(&self) -> Result<usize, ElfParseError> {
+        if let Some(ph) = self
+            .headers
+            .ph
+            .iter()
+            .find(|ph| ph.get_type() == Ok(Type::Phdr))
+        {
+            return Ok(ph.virtual_addr as usize + self.base);
+        }
+        self.phdr_from_load()
+    }
+
+    /// The virtual address of the program header table, or `mapped_addr` if
+    /// it is not mapped by the ELF file itself.
+    ///
+    /// When there is no `PT_PHDR` segment and no `LOAD` segment contains
+    /// `e_phoff` (see [`Self::phdr`]), the kernel maps the table on its own,
+    /// e.g. on the stack, and passes that address as `AT_PHDR`, see
+    /// [`AuxBuilder::phdr`].
+    pub fn phdr_fallback(&self, mapped_addr: usize) -> usize {
+        self.phdr().unwrap_or(mapped_addr)
+    }
+
+    /// Compute the virtual address of the program header table from the
+    /// `LOAD` segment containing it.
+    ///
+    /// The whole table must be in the file-backed part of the segment. Its
+    /// address is `p_vaddr` plus its distance from `p_offset`, which is not
+    /// `e_phoff` itself when the segment doesn't start at offset 0.
+    fn phdr_from_load(&self) -> Result<usize, ElfParseError> {
+        let ph_offset = self.headers.header.pt2.ph_offset();
+        let ph_end = ph_offset.saturating_add((self.phent() * self.phnum()) as u64);
         let header = self
             .headers
             .ph
             .iter()
+            .filter(|header| header.get_type() == Ok(Type::Load))
             .find(|header| {
-                (header.offset..header.offset + header.file_size).contains(&(ph_offset as u64))
+                header.offset <= ph_offset
+                    && ph_end <= header.offset.saturating_add(header.file_size)
             })
-            .expect("can not find program header table address in elf");
-        ph_offset - header.offset as usize + header.virtual_addr as usize + self.base
+            .ok_or(ElfParseError::PhdrNotMapped)?;
+        usize::try_from(ph_offset - header.offset)
+            .ok()
+            .and_then(|offset| offset.checked_add(header.virtual_addr as usize))
+            .and_then(|addr| addr.checked_add(self.base))
+            .ok_or(ElfParseError::AddressOverflow)
     }
 
     /// The base address of the ELF file loaded into the memory.
@@ -112,28 +664,1211 @@ impl<'a> ELFParser<'a> {
         self.headers
     }
 
+    /// Read all [`ELFPH`] with `LOAD` type of the elf file.
+    pub fn ph_load(&self) -> impl Iterator<Item = ELFPH> + '_ {
+        self.headers
+            .ph
+            .iter()
+            .filter(|ph| ph.get_type() == Ok(Type::Load))
+            .map(|ph| self.elfph(ph))
+    }
+
+    /// Read the writable `LOAD` segments, e.g. to map them privately
+    /// (copy-on-write).
+    pub fn writable_segments(&self) -> impl Iterator<Item = ELFPH> + '_ {
+        self.ph_load().filter(|ph| ph.is_write())
+    }
+
+    /// Read the `LOAD` segments that are not writable, such as `.text` and
+    /// `.rodata`, which may be shared between processes.
+    pub fn readonly_segments(&self) -> impl Iterator<Item = ELFPH> + '_ {
+        self.ph_load().filter(|ph| !ph.is_write())
+    }
+
+    /// Read all [`ELFPH`] with `LOAD` type sorted by `vaddr`.
+    ///
+    /// The ELF ABI requires the `LOAD` segments to be sorted, but some
+    /// toolchains emit them out of order. Segments with the same `vaddr` keep
+    /// the order of the program header table.
+    ///
+    /// With the `alloc` feature, the segments are collected and sorted in
+    /// O(n log n) time. Without it, nothing is allocated: each step picks the
+    /// next segment from the program header table, which takes O(n²) time for
+    /// the at most [`MAX_PHNUM`] segments.
+    pub fn load_segments_sorted(&self) -> impl Iterator<Item = ELFPH> + Clone + '_ {
+        #[cfg(feature = "alloc")]
+        {
+            let mut segments = self.ph_load().collect::<Vec<_>>();
+            // A stable sort, which keeps the order of equal `vaddr`s.
+            segments.sort_by_key(|ph| ph.vaddr);
+            segments.into_iter()
+        }
+        #[cfg(not(feature = "alloc"))]
+        {
+            // The `(vaddr, index)` of the last segment returned.
+            let mut last = None;
+            core::iter::from_fn(move || {
+                let (key, ph) = self
+                    .ph_load()
+                    .enumerate()
+                    .map(|(idx, ph)| ((ph.vaddr, idx), ph))
+                    .filter(|(key, _)| last.is_none_or(|last| *key > last))
+                    .min_by_key(|(key, _)| *key)?;
+                last = Some(key);
+                Some(ph)
+            })
+        }
+    }
+
+    /// The `LOAD` segment with the lowest `vaddr`, e.g. to place a guard page
+    /// below the image.
+    ///
+    /// Returns `None` if the elf file has no `LOAD` segment.
+    pub fn first_load(&self) -> Option<ELFPH> {
+        self.ph_load().min_by_key(|ph| ph.vaddr)
+    }
+
+    /// The `LOAD` segment with the highest `vaddr`.
+    ///
+    /// The program break (`brk`) typically starts right after it, see
+    /// [`Self::initial_brk`]. Returns `None` if the elf file has no `LOAD`
+    /// segment.
+    pub fn last_load(&self) -> Option<ELFPH> {
+        self.ph_load().max_by_key(|ph| ph.vaddr)
+    }
+
+    /// Read the `idx`-th program header of the elf file, whatever its type.
+    ///
+    /// Returns `None` if `idx` is not below [`Self::phnum`].
+    pub fn program_header(&self, idx: usize) -> Option<ELFPH> {
+        self.headers.ph.get(idx).map(|ph| self.elfph(ph))
+    }
+
+    /// Collect the `LOAD` segments of the elf file, like [`Self::ph_load`].
+    #[cfg(feature = "alloc")]
+    pub fn load_segments(&self) -> Vec<ELFPH> {
+        self.ph_load().collect()
+    }
+
+    /// Collect all program headers of the elf file, whatever their type.
+    ///
+    /// The `i`-th element is [`Self::program_header`] `(i)`.
+    #[cfg(feature = "alloc")]
+    pub fn all_segments(&self) -> Vec<ELFPH> {
+        self.headers.ph.iter().map(|ph| self.elfph(ph)).collect()
+    }
+
+    fn elfph(&self, ph: &ProgramHeader64) -> ELFPH {
+        ELFPH {
+            kind: raw_type(ph),
+            offset: ph.offset as usize,
+            vaddr: ph.virtual_addr as usize + self.base,
+            memsz: ph.mem_size,
+            filesz: ph.file_size,
+            flags: ph.flags,
+            align: ph.align,
+        }
+    }
+
+    /// The file-backed contents of the segment `ph`.
+    ///
+    /// `data` is the content of the whole ELF file. The returned slice is
+    /// exactly `ph.filesz` bytes long, to be copied to `ph.vaddr` before the
+    /// rest of the segment (see [`ELFPH::bss_range`]) is zeroed.
+    ///
+    /// Returns [`ElfParseError::Truncated`] if the contents are out of the
+    /// bounds of `data`.
+    pub fn segment_data<'d>(&self, data: &'d [u8], ph: &ELFPH) -> Result<&'d [u8], ElfParseError> {
+        ph.offset
+            .checked_add(ph.filesz as usize)
+            .and_then(|end| data.get(ph.offset..end))
+            .ok_or(ElfParseError::Truncated)
+    }
+
+    /// Read the file-backed contents of the segment `ph` from `src`, starting
+    /// `pos` bytes into the segment, e.g. page by page.
+    ///
+    /// Like [`Self::segment_data`], but the file is read on demand instead of
+    /// being held in memory. Any segment can be read, e.g. `PT_INTERP` to get
+    /// the path of the interpreter. Returns [`ElfParseError::Truncated`] if
+    /// `[pos, pos + buf.len())` exceeds `ph.filesz`, or if `src` fails.
+    pub fn read_segment_at(
+        &self,
+        src: &dyn ReadAt,
+        ph: &ELFPH,
+        pos: usize,
+        buf: &mut [u8],
+    ) -> Result<(), ElfParseError> {
+        let offset = pos
+            .checked_add(buf.len())
+            .filter(|&end| end as u64 <= ph.filesz)
+            .and_then(|_| ph.offset.checked_add(pos))
+            .ok_or(ElfParseError::Truncated)?;
+        src.read_at(offset, buf)
+    }
+
+    /// Read the `PT_NOTE` segment `ph` from `src` into `buf`, and iterate over
+    /// its notes.
+    ///
+    /// Like [`Self::notes`] for a single segment, but the file is read on
+    /// demand. If `buf` is shorter than the segment, only its first
+    /// `buf.len()` bytes are read and the notes stop at the truncated one.
+    pub fn read_notes<'b>(
+        &self,
+        src: &dyn ReadAt,
+        ph: &ELFPH,
+        buf: &'b mut [u8],
+    ) -> Result<impl Iterator<Item = Note<'b>>, ElfParseError> {
+        let len = buf.len().min(ph.filesz as usize);
+        self.read_segment_at(src, ph, 0, &mut buf[..len])?;
+        Ok(note_entries(&buf[..len], ph.align as usize))
+    }
+
+    /// How to zero the BSS of the segment `ph` after its file contents are
+    /// copied or mapped.
+    ///
+    /// The first element is the partial page containing `vaddr + filesz`, as
+    /// `(addr, len)`: only its tail from `addr` to the page end must be
+    /// zeroed, the file data before it must be kept. It is `None` if
+    /// `vaddr + filesz` is page-aligned. The second element is the range of
+    /// the remaining pages up to `page_up(vaddr + memsz)`, which can be
+    /// mapped as fresh zero pages. It is empty if the BSS ends in the partial
+    /// page.
+    ///
+    /// Returns `(None, end..end)` if the segment has no BSS, see
+    /// [`ELFPH::bss_range`], and `None` if the segment ends in the last page
+    /// of the address space, whose end can't be rounded up to `page_size`.
+    pub fn bss_zero_plan(&self, ph: &ELFPH, page_size: usize) -> Option<BssZeroPlan> {
+        let page_up = |addr: usize| Some(addr.checked_add(page_size - 1)? & !(page_size - 1));
+        let Some(bss) = ph.bss_range() else {
+            let end = page_up(ph.vaddr + ph.memsz as usize)?;
+            return Some((None, end..end));
+        };
+        let full_start = page_up(bss.start)?;
+        let partial = (full_start != bss.start).then(|| (bss.start, full_start - bss.start));
+        Some((partial, full_start..page_up(bss.end)?.max(full_start)))
+    }
+
+    /// The bytes of the file loaded at `[vaddr, vaddr + len)`.
+    ///
+    /// `data` is the content of the whole ELF file, and `vaddr` includes the
+    /// load base, e.g. the address of a GOT slot to read before patching it.
+    /// Returns `None` unless the range lies in the file-backed part of a
+    /// single `LOAD` segment: bytes in the zero-filled tail are not in the
+    /// file, and ranges spanning two segments are rejected.
+    pub fn read_at_vaddr<'d>(&self, data: &'d [u8], vaddr: usize, len: usize) -> Option<&'d [u8]> {
+        let vaddr = vaddr.checked_sub(self.base)?;
+        let offset = self.file_offset(vaddr as u64, len as u64)?;
+        data.get(offset..offset.checked_add(len)?)
+    }
+
+    /// The virtual address at which the byte at `file_offset` in the file is
+    /// loaded, including the load base.
+    ///
+    /// This is the inverse of [`Self::read_at_vaddr`], e.g. to find where a
+    /// section located by its file offset lies in memory. Returns `None` if
+    /// `file_offset` is not in the file-backed part of a `LOAD` segment, which
+    /// ends at the smaller of its `p_filesz` and `p_memsz`, or if the address
+    /// overflows.
+    pub fn vaddr_of_offset(&self, file_offset: usize) -> Option<usize> {
+        self.ph_load()
+            .find(|ph| {
+                ph.offset <= file_offset
+                    && (file_offset - ph.offset) < (ph.filesz as usize).min(ph.memsz as usize)
+            })
+            .and_then(|ph| (file_offset - ph.offset).checked_add(ph.vaddr))
+    }
+
+    /// Read all `LOAD` segments as page-aligned [`MapRegion`]s.
+    ///
+    /// Both the virtual address and the file offset are rounded down to
+    /// `page_size`, so `map_vaddr` must be mapped to `map_offset`, not to the
+    /// unrounded `p_offset`. This only keeps the data in place for segments
+    /// passing [`ELFPH::is_congruent`].
+    pub fn ph_load_aligned(&self, page_size: usize) -> impl Iterator<Item = MapRegion> + '_ {
+        self.ph_load_aligned_with(move |_| page_size)
+    }
+
+    /// Read all `LOAD` segments as [`MapRegion`]s aligned to the page size
+    /// `page_size_for(ph)` chosen for each segment, e.g. huge pages for a
+    /// large text segment and base pages for the others.
+    ///
+    /// Each region is rounded like in [`Self::ph_load_aligned`] with its own
+    /// page size. The data stays in place only if `p_offset` and `p_vaddr`
+    /// are congruent modulo that size, which holds for sizes up to the
+    /// `p_align` of a segment passing [`ELFPH::is_congruent`]. The regions of
+    /// adjacent segments may overlap on a huge page.
+    pub fn ph_load_aligned_with(
+        &self,
+        page_size_for: impl Fn(&ELFPH) -> usize,
+    ) -> impl Iterator<Item = MapRegion> {
+        self.ph_load()
+            .map(move |ph| MapRegion::new(&ph, page_size_for(&ph)))
+    }
+
+    /// Check that the entry point lies in an executable `LOAD` segment.
+    ///
+    /// Returns [`ElfParseError::BadEntry`] otherwise, as the process would
+    /// fault on its first instruction. For a dynamically linked executable,
+    /// the process starts at the entry point of its interpreter, so the
+    /// check is to be run on the interpreter as well.
+    pub fn validate_entry(&self) -> Result<(), ElfParseError> {
+        let entry = self.entry();
+        if self.ph_load().any(|ph| {
+            ph.flags.is_execute() && ph.vaddr <= entry && entry - ph.vaddr < ph.memsz as usize
+        }) {
+            Ok(())
+        } else {
+            Err(ElfParseError::BadEntry)
+        }
+    }
+
+    /// Check that no two `LOAD` segments overlap in the virtual memory.
+    ///
+    /// The byte ranges `[vaddr, vaddr + memsz)` are compared, so segments may
+    /// still share a partial page as the ABI allows. Returns
+    /// [`ElfParseError::OverlappingSegments`] otherwise.
+    pub fn validate_segments(&self) -> Result<(), ElfParseError> {
+        let ranges = self
+            .load_segments_sorted()
+            .map(|ph| ph.vaddr..ph.vaddr.saturating_add(ph.memsz as usize));
+        if ranges
+            .clone()
+            .zip(ranges.skip(1))
+            .any(|(prev, next)| prev.end > next.start)
+        {
+            return Err(ElfParseError::OverlappingSegments);
+        }
+        Ok(())
+    }
+
+    /// Check that no `LOAD` segment starts below `min_addr`, e.g. the
+    /// `mmap_min_addr` of the kernel, so that the program can't map the null
+    /// page.
+    ///
+    /// The addresses include the load base, so a PIE usually passes as long
+    /// as the base is high enough, while an executable may ask for a low
+    /// fixed address. Returns [`ElfParseError::MapsBelowMin`] otherwise.
+    pub fn validate_min_addr(&self, min_addr: usize) -> Result<(), ElfParseError> {
+        if self.ph_load().any(|ph| ph.vaddr < min_addr) {
+            return Err(ElfParseError::MapsBelowMin);
+        }
+        Ok(())
+    }
+
+    /// Check that `p_offset` and `p_vaddr` of every `LOAD` segment are
+    /// congruent modulo `p_align`, as the ELF ABI requires to map the
+    /// segments from the file.
+    ///
+    /// The link-time addresses are checked, see [`ELFPH::is_congruent`] to
+    /// also take the load base into account. Segments with a `p_align` of 0
+    /// or 1 are not constrained. Returns [`ElfParseError::MisalignedSegment`]
+    /// otherwise.
+    pub fn validate_congruence(&self) -> Result<(), ElfParseError> {
+        let misaligned = self
+            .headers
+            .ph
+            .iter()
+            .filter(|ph| ph.get_type() == Ok(Type::Load) && ph.align > 1)
+            .any(|ph| ph.offset % ph.align != ph.virtual_addr % ph.align);
+        if misaligned {
+            return Err(ElfParseError::MisalignedSegment);
+        }
+        Ok(())
+    }
+
+    /// Check that the file contents of the segments lie within the ELF file.
+    ///
+    /// `file_size` is the size of the whole ELF file. The range
+    /// `[offset, offset + filesz)` of every `LOAD`, `INTERP`, `DYNAMIC` and
+    /// `NOTE` segment is checked, so that copying their contents can't read
+    /// past the end of the file. Returns [`ElfParseError::Truncated`]
+    /// otherwise.
+    pub fn validate_offsets(&self, file_size: usize) -> Result<(), ElfParseError> {
+        let truncated = self
+            .headers
+            .ph
+            .iter()
+            .filter(|ph| {
+                matches!(
+                    ph.get_type(),
+                    Ok(Type::Load | Type::Interp | Type::Dynamic | Type::Note)
+                )
+            })
+            .any(|ph| {
+                ph.offset
+                    .checked_add(ph.file_size)
+                    .is_none_or(|end| end > file_size as u64)
+            });
+        if truncated {
+            return Err(ElfParseError::Truncated);
+        }
+        Ok(())
+    }
+
+    /// Whether any `LOAD` segment is both writable and executable.
+    ///
+    /// Callers enforcing a W^X policy can use this to warn about or refuse
+    /// such files before mapping them.
+    pub fn has_wx_segment(&self) -> bool {
+        self.ph_load()
+            .any(|ph| ph.flags.is_write() && ph.flags.is_execute())
+    }
+
+    /// Read all `LOAD` segments as page-aligned [`MapRegion`]s, merging
+    /// adjacent ones that can share a single mapping.
+    ///
+    /// Regions are merged if they have the same flags and are contiguous (or
+    /// share a page) in both the virtual memory and the file. A region with
+    /// `.bss` is never merged with the next one, so that its zero-filled tail
+    /// is not covered by file data. The regions are sorted by virtual address
+    /// and never overlap. Empty segments, which map no page, are skipped.
+    ///
+    /// Segments with different flags may still share the page at their
+    /// boundary, e.g. `.text` ending and `.data` starting in the same page
+    /// when linked without page-aligned segments. That page is split out as
+    /// a region of its own, with the union of the flags of both segments, so
+    /// that the data in it stays writable and the code executable. Like
+    /// Linux, which maps the second segment over the first, the page is
+    /// mapped from the file page of the second segment: the linker lays such
+    /// segments out contiguously in the file, so it holds the data of both.
+    ///
+    /// All segments are rounded to the same `page_size`, see
+    /// [`Self::merged_load_regions_with`] to choose it per segment.
+    pub fn merged_load_regions(&self, page_size: usize) -> impl Iterator<Item = MapRegion> + '_ {
+        self.merged_load_regions_with(move |_| page_size)
+    }
+
+    /// Read all `LOAD` segments as [`MapRegion`]s aligned to the page size
+    /// `page_size_for(ph)` chosen for each segment, merging adjacent ones like
+    /// [`Self::merged_load_regions`].
+    ///
+    /// Only regions with the same page size are merged, or have their shared
+    /// page split out. A huge page can't be split into base pages without
+    /// losing its alignment, so regions with different page sizes are left as
+    /// in [`Self::ph_load_aligned_with`], and may overlap on a huge page.
+    pub fn merged_load_regions_with(
+        &self,
+        page_size_for: impl Fn(&ELFPH) -> usize,
+    ) -> impl Iterator<Item = MapRegion> {
+        let mut segments = self.load_segments_sorted();
+        // The last region, which may still grow or be split, whether it has
+        // `.bss`, and its page size.
+        let mut last: Option<(MapRegion, bool, usize)> = None;
+        // The regions completed by the current segment, in order.
+        let mut done: [Option<MapRegion>; 2] = [None, None];
+        core::iter::from_fn(move || {
+            loop {
+                if let Some(region) = done.iter_mut().find_map(Option::take) {
+                    return Some(region);
+                }
+                let Some(ph) = segments.next() else {
+                    return last.take().map(|(region, ..)| region);
+                };
+                let page_size = page_size_for(&ph);
+                let mut region = MapRegion::new(&ph, page_size);
+                if region.map_size == 0 {
+                    continue;
+                }
+                let has_bss = ph.memsz > ph.filesz;
+                if let Some((prev, prev_has_bss, prev_page_size)) = &mut last
+                    && *prev_page_size == page_size
+                {
+                    let prev_end = prev.map_vaddr + prev.map_size;
+                    let contiguous = prev_end >= region.map_vaddr
+                        && prev.map_offset + (region.map_vaddr - prev.map_vaddr)
+                            == region.map_offset;
+                    if !*prev_has_bss && prev.flags == region.flags && contiguous {
+                        prev.map_size =
+                            prev_end.max(region.map_vaddr + region.map_size) - prev.map_vaddr;
+                        *prev_has_bss = has_bss;
+                        continue;
+                    }
+                    if prev_end == region.map_vaddr + page_size {
+                        // Move the shared page out of both regions.
+                        let mut shared = MapRegion {
+                            map_vaddr: region.map_vaddr,
+                            map_offset: region.map_offset,
+                            map_size: page_size,
+                            flags: prev.flags | region.flags,
+                            first_page_pad: 0,
+                        };
+                        let shared_has_bss = *prev_has_bss;
+                        prev.map_size -= page_size;
+                        if prev.map_size == 0 {
+                            // The first segment starts in the shared page.
+                            shared.first_page_pad = prev.first_page_pad;
+                            last = None;
+                        }
+                        region.map_vaddr += page_size;
+                        region.map_offset += page_size;
+                        region.map_size -= page_size;
+                        region.first_page_pad = 0;
+                        done[0] = last.take().map(|(region, ..)| region);
+                        if region.map_size == 0 {
+                            last = Some((shared, shared_has_bss || has_bss, page_size));
+                            continue;
+                        }
+                        done[1] = Some(shared);
+                        last = Some((region, has_bss, page_size));
+                        continue;
+                    }
+                }
+                done[0] = last
+                    .replace((region, has_bss, page_size))
+                    .map(|(region, ..)| region);
+            }
+        })
+    }
+
+    /// The virtual address range spanned by all `LOAD` segments, rounded out
+    /// to `page_size`.
+    ///
+    /// The range is adjusted by the load base, so it is the relative span for
+    /// a PIE parsed with a zero bias and the absolute span for an executable.
+    /// It is empty (`0..0`) if the ELF file has no `LOAD` segment.
+    ///
+    /// Returns `None` if a segment ends in the last page of the address
+    /// space, whose end can't be rounded up to `page_size`.
+    pub fn image_range(&self, page_size: usize) -> Option<Range<usize>> {
+        let start = self.ph_load().map(|ph| ph.vaddr).min();
+        let end = self.ph_load().map(|ph| ph.vaddr + ph.memsz as usize).max();
+        match (start, end) {
+            (Some(start), Some(end)) => {
+                let end = end.checked_add(page_size - 1)? & !(page_size - 1);
+                Some((start & !(page_size - 1))..end)
+            }
+            _ => Some(0..0),
+        }
+    }
+
+    /// The bias to pass to [`ELFParser::new`] so that the image starts at
+    /// `desired_base`.
+    ///
+    /// That is `desired_base - page_down(min_vaddr)`, where `min_vaddr` is the
+    /// lowest link-time `vaddr` of the `LOAD` segments, whatever the bias of
+    /// this parser. The first segment then lands on the page at
+    /// `desired_base`, even if it is linked at a nonzero or unaligned address.
+    /// `desired_base` should be aligned to `page_size`.
+    ///
+    /// The result is meaningless for executables, whose bias is ignored.
+    pub fn load_bias_for(&self, desired_base: usize, page_size: usize) -> usize {
+        let min_vaddr = self
+            .headers
+            .ph
+            .iter()
+            .filter(|ph| ph.get_type() == Ok(Type::Load))
+            .map(|ph| ph.virtual_addr as usize)
+            .min()
+            .unwrap_or(0);
+        desired_base.wrapping_sub(min_vaddr & !(page_size - 1))
+    }
+
+    /// The initial program break (`brk`), right after the loaded image.
+    ///
+    /// That is `page_up(vaddr + memsz)` over the `LOAD` segments, including
+    /// the load base, so the zero-filled BSS is below the break. The
+    /// interpreter is not taken into account: the break follows the main
+    /// program even if the interpreter is mapped above it. Returns `Some(0)`
+    /// if the ELF file has no `LOAD` segment, and `None` if the image ends in
+    /// the last page of the address space, see [`Self::image_range`].
+    pub fn initial_brk(&self, page_size: usize) -> Option<usize> {
+        self.image_range(page_size).map(|range| range.end)
+    }
+
+    /// The number of pages spanned by all `LOAD` segments, including the
+    /// holes between them.
+    ///
+    /// This is the length of [`image_range`](Self::image_range) in pages, or
+    /// `None` if that range can't be computed.
+    pub fn page_count(&self, page_size: usize) -> Option<usize> {
+        self.image_range(page_size)
+            .map(|range| range.len() / page_size)
+    }
+
+    /// Gather what a loader needs to map and start the ELF file in one call.
+    ///
+    /// `data` is the content of the whole ELF file, and `page_size` the page
+    /// size used to compute the [`LoadPlan::image`], [`LoadPlan::regions`]
+    /// and [`LoadPlan::relro`]. Errors are reported as in [`Self::interp`],
+    /// and [`ElfParseError::AddressOverflow`] is returned if the image ends
+    /// in the last page of the address space, see [`Self::image_range`].
+    #[cfg(feature = "alloc")]
+    pub fn plan<'d>(
+        &self,
+        data: &'d [u8],
+        page_size: usize,
+    ) -> Result<LoadPlan<'d>, ElfParseError> {
+        Ok(LoadPlan {
+            entry: self.entry(),
+            image: self
+                .image_range(page_size)
+                .ok_or(ElfParseError::AddressOverflow)?,
+            regions: self.merged_load_regions(page_size).collect(),
+            interp: self.interp(data)?,
+            relro: self.relro(page_size),
+            executable_stack: self.executable_stack(),
+        })
+    }
+
+    /// The strictest alignment required by the `LOAD` segments, at least
+    /// `page_size`.
+    ///
+    /// A base aligned to it keeps every segment aligned to its `p_align`, e.g.
+    /// to preserve huge-page eligibility. A `p_align` of 0 or 1 means no
+    /// constraint, so the result is `page_size` if no segment declares more.
+    pub fn max_align(&self, page_size: usize) -> u64 {
+        self.ph_load()
+            .map(|ph| ph.align)
+            .fold(page_size as u64, u64::max)
+    }
+
+    /// The number of bytes spanned by all `LOAD` segments, from the lowest
+    /// `vaddr` to the highest `vaddr + memsz`, without page rounding.
+    pub fn memory_size(&self) -> usize {
+        // Rounding to 1 byte never overflows.
+        self.image_range(1).unwrap_or_default().len()
+    }
+
+    /// The number of bytes copied from the file for all `LOAD` segments, i.e.
+    /// the sum of their `filesz`.
+    pub fn total_file_bytes(&self) -> u64 {
+        self.ph_load().map(|ph| ph.filesz).sum()
+    }
+
+    /// The number of bytes of memory taken by all `LOAD` segments, i.e. the
+    /// sum of their `memsz`.
+    ///
+    /// Unlike [`Self::memory_size`], the holes between the segments are not
+    /// counted.
+    pub fn total_mem_bytes(&self) -> u64 {
+        self.ph_load().map(|ph| ph.memsz).sum()
+    }
+
+    /// The thread-local storage template of the ELF file.
+    ///
+    /// Returns `None` if the ELF file has no `PT_TLS` segment.
+    pub fn tls_template(&self) -> Option<TlsInfo> {
+        self.headers
+            .ph
+            .iter()
+            .find(|ph| ph.get_type() == Ok(Type::Tls))
+            .map(|ph| TlsInfo {
+                vaddr: ph.virtual_addr as usize + self.base,
+                filesz: ph.file_size,
+                memsz: ph.mem_size,
+                align: ph.align,
+            })
+    }
+
+    /// The virtual address range covered by the `PT_GNU_RELRO` segment.
+    ///
+    /// The region overlaps the `LOAD` segments: it is not a new mapping, but
+    /// a range to be made read-only (e.g. by `mprotect`) once relocations are
+    /// applied. Like glibc, both ends are rounded down to `page_size`, as the
+    /// last partial page is shared with writable data. The returned range may
+    /// thus be empty.
+    ///
+    /// Returns `None` if the ELF file has no `PT_GNU_RELRO` segment.
+    pub fn relro(&self, page_size: usize) -> Option<Range<usize>> {
+        self.headers
+            .ph
+            .iter()
+            .find(|ph| ph.get_type() == Ok(Type::GnuRelro))
+            .map(|ph| {
+                let start = ph.virtual_addr as usize + self.base;
+                let end = start + ph.mem_size as usize;
+                (start & !(page_size - 1))..(end & !(page_size - 1))
+            })
+    }
+
+    /// The virtual address range of the `.eh_frame_hdr` section, covered by
+    /// the `PT_GNU_EH_FRAME` segment.
+    ///
+    /// The range is adjusted by the load base, and is meant to be registered
+    /// with an unwinder. Returns `None` if the ELF file has no such segment,
+    /// e.g. when compiled with `-fno-asynchronous-unwind-tables`.
+    pub fn eh_frame_hdr(&self) -> Option<Range<usize>> {
+        self.headers
+            .ph
+            .iter()
+            .find(|ph| ph.get_type() == Ok(Type::OsSpecific(PT_GNU_EH_FRAME)))
+            .map(|ph| {
+                let start = ph.virtual_addr as usize + self.base;
+                start..start + ph.mem_size as usize
+            })
+    }
+
+    /// Whether the stack should be mapped executable.
+    ///
+    /// This is the execute permission of the `PT_GNU_STACK` segment. Without
+    /// such a segment, the stack is non-executable.
+    pub fn executable_stack(&self) -> bool {
+        self.headers
+            .ph
+            .iter()
+            .find(|ph| ph.get_type() == Ok(Type::OsSpecific(PT_GNU_STACK)))
+            .is_some_and(|ph| ph.flags.is_execute())
+    }
+
+    /// The notes in the `PT_NOTE` segments of the ELF file.
+    ///
+    /// `data` is the content of the whole ELF file. Segments out of its
+    /// bounds are skipped, and the notes of a segment stop at the first
+    /// truncated one.
+    pub fn notes<'d>(&self, data: &'d [u8]) -> impl Iterator<Item = Note<'d>> {
+        self.headers
+            .ph
+            .iter()
+            .filter(|ph| ph.get_type() == Ok(Type::Note))
+            .filter_map(move |ph| {
+                let end = (ph.offset as usize).checked_add(ph.file_size as usize)?;
+                Some((data.get(ph.offset as usize..end)?, ph.align as usize))
+            })
+            .flat_map(|(notes, align)| note_entries(notes, align))
+    }
+
+    /// The GNU build-id of the ELF file.
+    ///
+    /// `data` is the content of the whole ELF file. The id is the descriptor
+    /// of the `NT_GNU_BUILD_ID` note owned by `"GNU"` in the `PT_NOTE`
+    /// segments. Returns `None` if there is no such note.
+    pub fn build_id<'d>(&self, data: &'d [u8]) -> Option<&'d [u8]> {
+        self.notes(data)
+            .find(|note| note.name == "GNU" && note.kind == NT_GNU_BUILD_ID)
+            .map(|note| note.desc)
+    }
+
+    /// The control-flow integrity features supported by the ELF file.
+    ///
+    /// `data` is the content of the whole ELF file. Like Linux, only the
+    /// `.note.gnu.property` notes in the `PT_GNU_PROPERTY` segment are read.
+    /// All features are off if there is no such segment, or if it is out of
+    /// the bounds of `data`.
+    pub fn gnu_properties(&self, data: &[u8]) -> GnuProperties {
+        let mut properties = GnuProperties::default();
+        let Some(ph) = self
+            .headers
+            .ph
+            .iter()
+            .find(|ph| ph.get_type() == Ok(Type::OsSpecific(PT_GNU_PROPERTY)))
+        else {
+            return properties;
+        };
+        let Some(notes) = (ph.offset as usize)
+            .checked_add(ph.file_size as usize)
+            .and_then(|end| data.get(ph.offset as usize..end))
+        else {
+            return properties;
+        };
+        let align = match self.headers.header.pt1.class() {
+            Class::ThirtyTwo => 4,
+            _ => 8,
+        };
+        for note in note_entries(notes, align) {
+            if note.name == "GNU" && note.kind == NT_GNU_PROPERTY_TYPE_0 {
+                properties.add(note.desc, align, self.machine());
+            }
+        }
+        properties
+    }
+
+    /// The minimum Linux kernel version required by the ELF file, as
+    /// `(major, minor, patch)`.
+    ///
+    /// `data` is the content of the whole ELF file. The version is read from
+    /// the `NT_GNU_ABI_TAG` note owned by `"GNU"` (`.note.ABI-tag`), which
+    /// glibc links into executables. Returns `None` if there is no such note,
+    /// or if it is for another OS.
+    pub fn min_kernel_version(&self, data: &[u8]) -> Option<(u32, u32, u32)> {
+        let note = self
+            .notes(data)
+            .find(|note| note.name == "GNU" && note.kind == NT_GNU_ABI_TAG)?;
+        let word = |i: usize| {
+            let bytes = note.desc.get(i * 4..i * 4 + 4)?;
+            Some(u32::from_ne_bytes(bytes.try_into().unwrap()))
+        };
+        if word(0)? != ELF_NOTE_OS_LINUX {
+            return None;
+        }
+        Some((word(1)?, word(2)?, word(3)?))
+    }
+
+    /// Parse the dynamic section of the ELF file.
+    ///
+    /// `data` is the content of the whole ELF file. Unknown tags are ignored.
+    ///
+    /// Returns `Ok(None)` if the ELF file has no `PT_DYNAMIC` segment,
+    /// [`ElfParseError::Truncated`] if the segment is out of the bounds of
+    /// `data` and [`ElfParseError::BadDynamic`] if it is not terminated by
+    /// `DT_NULL` or if an address in it overflows once adjusted by the load
+    /// base.
+    pub fn dynamic(&self, data: &[u8]) -> Result<Option<DynamicInfo>, ElfParseError> {
+        let Some(section) = self.dynamic_section(data)? else {
+            return Ok(None);
+        };
+        let entries = dynamic_entries(section, self.headers.header.pt1.class())?;
+        DynamicInfo::parse(entries, self.base).map(Some)
+    }
+
+    /// The names of the shared libraries required by the ELF file, from its
+    /// `DT_NEEDED` entries.
+    ///
+    /// `data` is the content of the whole ELF file. The iterator is empty if
+    /// the ELF file has no dynamic section, e.g. for static executables.
+    ///
+    /// Returns [`ElfParseError::Truncated`] if the dynamic string table is
+    /// not in the file, and [`ElfParseError::BadDynamic`] if a name is out
+    /// of its bounds or not valid UTF-8.
+    pub fn needed_libs<'d>(
+        &self,
+        data: &'d [u8],
+    ) -> Result<impl Iterator<Item = &'d str> + 'd, ElfParseError> {
+        let (entries, strtab) = match self.dynamic_section(data)? {
+            Some(section) => {
+                let entries = dynamic_entries(section, self.headers.header.pt1.class())?;
+                let info = DynamicInfo::parse(entries.clone(), self.base)?;
+                (Some(entries), self.dynamic_strtab(data, &info)?)
+            }
+            None => (None, &[][..]),
+        };
+        let names = entries
+            .into_iter()
+            .flatten()
+            .filter(|&(tag, _)| tag == DT_NEEDED)
+            .map(move |(_, offset)| {
+                string_at(strtab, offset as usize).and_then(|name| core::str::from_utf8(name).ok())
+            });
+        if names.clone().any(|name| name.is_none()) {
+            return Err(ElfParseError::BadDynamic);
+        }
+        Ok(names.flatten())
+    }
+
+    /// The `DT_SONAME` of a shared object, the name other ELF files refer to
+    /// it by in their `DT_NEEDED` entries.
+    ///
+    /// `data` is the content of the whole ELF file. Returns `Ok(None)` if the
+    /// ELF file has no dynamic section or no soname, e.g. for executables.
+    /// Errors are reported as in [`Self::needed_libs`].
+    pub fn soname<'d>(&self, data: &'d [u8]) -> Result<Option<&'d str>, ElfParseError> {
+        self.dynamic_string(data, DT_SONAME)
+    }
+
+    /// The `DT_RUNPATH` library search path of the ELF file.
+    ///
+    /// `data` is the content of the whole ELF file. The colon-separated list
+    /// is returned as is: tokens such as `$ORIGIN` are not expanded. Returns
+    /// `Ok(None)` if there is no such entry. Errors are reported as in
+    /// [`Self::needed_libs`].
+    pub fn runpath<'d>(&self, data: &'d [u8]) -> Result<Option<&'d str>, ElfParseError> {
+        self.dynamic_string(data, DT_RUNPATH)
+    }
+
+    /// The legacy `DT_RPATH` library search path of the ELF file.
+    ///
+    /// Like [`Self::runpath`], the string is returned as is. Dynamic linkers
+    /// ignore it when `DT_RUNPATH` is also present.
+    pub fn rpath<'d>(&self, data: &'d [u8]) -> Result<Option<&'d str>, ElfParseError> {
+        self.dynamic_string(data, DT_RPATH)
+    }
+
+    /// Resolve the first entry with `tag` in the dynamic section through the
+    /// dynamic string table.
+    fn dynamic_string<'d>(
+        &self,
+        data: &'d [u8],
+        tag: u64,
+    ) -> Result<Option<&'d str>, ElfParseError> {
+        let Some(section) = self.dynamic_section(data)? else {
+            return Ok(None);
+        };
+        let entries = dynamic_entries(section, self.headers.header.pt1.class())?;
+        let Some((_, offset)) = entries.clone().find(|&(t, _)| t == tag) else {
+            return Ok(None);
+        };
+        let strtab = self.dynamic_strtab(data, &DynamicInfo::parse(entries, self.base)?)?;
+        string_at(strtab, offset as usize)
+            .and_then(|name| core::str::from_utf8(name).ok())
+            .map(Some)
+            .ok_or(ElfParseError::BadDynamic)
+    }
+
+    /// The content of the `PT_DYNAMIC` segment, if any.
+    fn dynamic_section<'d>(&self, data: &'d [u8]) -> Result<Option<&'d [u8]>, ElfParseError> {
+        let Some(ph) = self
+            .headers
+            .ph
+            .iter()
+            .find(|ph| ph.get_type() == Ok(Type::Dynamic))
+        else {
+            return Ok(None);
+        };
+        (ph.offset as usize)
+            .checked_add(ph.file_size as usize)
+            .and_then(|end| data.get(ph.offset as usize..end))
+            .map(Some)
+            .ok_or(ElfParseError::Truncated)
+    }
+
+    /// The dynamic string table `DT_STRTAB` in `data`, or an empty table if
+    /// there is none.
+    fn dynamic_strtab<'d>(
+        &self,
+        data: &'d [u8],
+        info: &DynamicInfo,
+    ) -> Result<&'d [u8], ElfParseError> {
+        let Some(strtab) = info.strtab else {
+            return Ok(&[]);
+        };
+        self.file_offset((strtab - self.base) as u64, info.strsz)
+            .and_then(|offset| data.get(offset..offset.checked_add(info.strsz as usize)?))
+            .ok_or(ElfParseError::Truncated)
+    }
+
+    /// Whether the ELF file has relocations to apply, i.e. whether its
+    /// dynamic section refers to a non-empty `DT_RELA`, `DT_REL`, `DT_RELR`
+    /// or `DT_JMPREL` table.
+    ///
+    /// `data` is the content of the whole ELF file. Only the dynamic section
+    /// is read, not the tables. Returns `false` if there is no dynamic
+    /// section, e.g. for static non-PIE executables, or if it can't be read.
+    pub fn has_relocations(&self, data: &[u8]) -> bool {
+        let Ok(Some(info)) = self.dynamic(data) else {
+            return false;
+        };
+        [
+            (info.rela, info.relasz),
+            (info.rel, info.relsz),
+            (info.relr, info.relrsz),
+            (info.jmprel, info.pltrelsz),
+        ]
+        .into_iter()
+        .any(|(table, size)| table.is_some() && size != 0)
+    }
+
+    /// The relocations in the `DT_RELA`, `DT_JMPREL` and `DT_RELR` tables of
+    /// the ELF file.
+    ///
+    /// `data` is the content of the whole ELF file. The relocations of the
+    /// `DT_RELA` table come first, then those of the PLT (`DT_JMPREL`, if
+    /// `DT_PLTREL` is `DT_RELA`), where the linker places `R_*_IRELATIVE`
+    /// relocations of static executables. They are followed by the
+    /// `R_*_RELATIVE` ones implied by the compact `DT_RELR` table, whose
+    /// addends are read from `data`. The iterator is empty if the ELF file has
+    /// no dynamic section or no such table, e.g. for static non-PIE
+    /// executables.
+    ///
+    /// Returns [`ElfParseError::UnsupportedArch`] if the ELF file has a
+    /// `DT_RELR` table but the relocation types of the machine are unknown,
+    /// and [`ElfParseError::BadDynamic`] if the address of a relocation
+    /// overflows once adjusted by the load base.
+    pub fn relocations<'d>(
+        &self,
+        data: &'d [u8],
+    ) -> Result<impl Iterator<Item = Relocation> + 'd, ElfParseError> {
+        let class = self.headers.header.pt1.class();
+        let (min_entsize, word) = match class {
+            Class::ThirtyTwo => (12, 4),
+            _ => (24, 8),
+        };
+        let info = self.dynamic(data)?.unwrap_or_default();
+        let mut table: &[u8] = &[];
+        let mut entsize = min_entsize;
+        if let Some(rela) = info.rela {
+            if info.relaent != 0 {
+                entsize = info.relaent as usize;
+            }
+            if entsize < min_entsize {
+                return Err(ElfParseError::BadDynamic);
+            }
+            table = self.table(data, rela, info.relasz)?;
+        }
+        let mut plt: &[u8] = &[];
+        if let Some(jmprel) = info.jmprel.filter(|_| info.pltrel == DT_RELA) {
+            plt = self.table(data, jmprel, info.pltrelsz)?;
+        }
+
+        let types = reloc_kinds_for(self.machine());
+        let mut relr: &[u8] = &[];
+        let mut relative = 0;
+        if let Some(addr) = info.relr {
+            if info.relrent != 0 && info.relrent != word as u64 {
+                return Err(ElfParseError::BadDynamic);
+            }
+            relative = types.ok_or(ElfParseError::UnsupportedArch)?.relative;
+            relr = self.table(data, addr, info.relrsz)?;
+        }
+        // The implicit addend of a `DT_RELR` relocation is the word at its
+        // address in the file.
+        let phs = if relr.is_empty() {
+            ProgramHeaders::default()
+        } else {
+            self.headers.ph.clone()
+        };
+        let addend_at = move |vaddr: u64| {
+            let offset = file_offset_in(&phs, vaddr, word as u64)?;
+            let bytes = data.get(offset..offset.checked_add(word)?)?;
+            Some(match word {
+                4 => u32::from_ne_bytes(bytes.try_into().unwrap()) as i32 as i64,
+                _ => u64::from_ne_bytes(bytes.try_into().unwrap()) as i64,
+            })
+        };
+        if relr_entries(relr, class).any(|vaddr| addend_at(vaddr).is_none()) {
+            return Err(ElfParseError::Truncated);
+        }
+        let base = self.base;
+        let biased = move |vaddr: u64| usize::try_from(vaddr).ok()?.checked_add(base);
+        if relr_entries(relr, class).any(|vaddr| biased(vaddr).is_none()) {
+            return Err(ElfParseError::BadDynamic);
+        }
+        let implied = relr_entries(relr, class).map(move |vaddr| Relocation {
+            offset: biased(vaddr).unwrap(),
+            kind: relative,
+            category: RelocKind::Relative,
+            sym_index: None,
+            addend: addend_at(vaddr).unwrap(),
+        });
+        let explicit = rela_entries(table, entsize, class, base, types)
+            .chain(rela_entries(plt, entsize, class, base, types));
+        if explicit.clone().any(|reloc| reloc.is_none()) {
+            return Err(ElfParseError::BadDynamic);
+        }
+        Ok(explicit.flatten().chain(implied))
+    }
+
+    /// The table of `size` bytes at the biased address `addr` in `data`.
+    fn table<'d>(&self, data: &'d [u8], addr: usize, size: u64) -> Result<&'d [u8], ElfParseError> {
+        self.file_offset((addr - self.base) as u64, size)
+            .and_then(|offset| data.get(offset..offset.checked_add(size as usize)?))
+            .ok_or(ElfParseError::Truncated)
+    }
+
+    /// Apply the `R_*_RELATIVE` relocations of the ELF file.
+    ///
+    /// `data` is the content of the whole ELF file. For each relocation,
+    /// `write(addr, value)` is called to store `value = base + addend` at the
+    /// biased address `addr`. Other relocation types are skipped.
+    ///
+    /// Returns [`ElfParseError::UnsupportedArch`] if the relocation types of
+    /// the machine are unknown.
+    pub fn apply_relative(
+        &self,
+        data: &[u8],
+        mut write: impl FnMut(usize, usize),
+    ) -> Result<(), ElfParseError> {
+        reloc_kinds_for(self.machine()).ok_or(ElfParseError::UnsupportedArch)?;
+        for reloc in self
+            .relocations(data)?
+            .filter(|r| r.category == RelocKind::Relative)
+        {
+            write(reloc.offset, self.base.wrapping_add(reloc.addend as usize));
+        }
+        Ok(())
+    }
+
+    /// Apply the `R_*_IRELATIVE` relocations of the ELF file.
+    ///
+    /// `data` is the content of the whole ELF file. For each relocation,
+    /// `call_resolver(addr)` is called with the biased address `base + addend`
+    /// of an IFUNC resolver, and `write(addr, value)` stores the address it
+    /// returns at the biased address `addr`.
+    ///
+    /// The resolver is code of the loaded image: it must be run in the target
+    /// address space, after the segments are mapped and the `R_*_RELATIVE`
+    /// relocations (see [`Self::apply_relative`]) are applied, as it may read
+    /// relocated data or CPU features.
+    ///
+    /// Returns [`ElfParseError::UnsupportedArch`] if the relocation types of
+    /// the machine are unknown.
+    pub fn apply_irelative(
+        &self,
+        data: &[u8],
+        mut call_resolver: impl FnMut(usize) -> usize,
+        mut write: impl FnMut(usize, usize),
+    ) -> Result<(), ElfParseError> {
+        reloc_kinds_for(self.machine()).ok_or(ElfParseError::UnsupportedArch)?;
+        for reloc in self
+            .relocations(data)?
+            .filter(|r| r.category == RelocKind::IRelative)
+        {
+            let resolver = self.base.wrapping_add(reloc.addend as usize);
+            write(reloc.offset, call_resolver(resolver));
+        }
+        Ok(())
+    }
+
+    /// Look up the address of the defined symbol `name`.
+    ///
+    /// `data` is the content of the whole ELF file. The dynamic symbol table
+    /// (`.dynsym`) is searched first, then `.symtab` if present. Both are
+    /// found through the section headers, so nothing is found in files
+    /// stripped of them. Undefined symbols are skipped, and `None` is
+    /// returned if the address of the symbol overflows once adjusted by the
+    /// load base.
+    ///
+    /// The lookup is a linear scan of the tables: it takes O(n) time in the
+    /// number of symbols, as the `.hash` and `.gnu.hash` tables are not used
+    /// yet.
+    pub fn symbol(&self, data: &[u8], name: &str) -> Option<usize> {
+        let class = self.headers.header.pt1.class();
+        [SHT_DYNSYM, SHT_SYMTAB].into_iter().find_map(|sh_type| {
+            let table = self.raw_sections(data).find(|s| s.sh_type == sh_type)?;
+            let strtab = self
+                .raw_sections(data)
+                .nth(table.link as usize)?
+                .data(data)?;
+            symbol_entries(table.data(data)?, table.entsize as usize, class)
+                .filter(|sym| sym.shndx != SHN_UNDEF)
+                .find(|sym| string_at(strtab, sym.name as usize) == Some(name.as_bytes()))
+                .and_then(|sym| match sym.shndx {
+                    SHN_ABS => Some(sym.value as usize),
+                    _ => (sym.value as usize).checked_add(self.base),
+                })
+        })
+    }
+
+    /// The name of the symbol at `index` in the dynamic symbol table.
+    ///
+    /// `data` is the content of the whole ELF file. This maps the
+    /// [`Relocation::sym_index`] of a relocation to the name to be resolved
+    /// by the caller. The tables are found through `DT_SYMTAB` and
+    /// `DT_STRTAB`, so unlike [`Self::symbol`] this works for files stripped
+    /// of section headers.
+    ///
+    /// Returns `None` if the file has no dynamic symbol table, or if the
+    /// entry or its name is out of the bounds of the file or not UTF-8.
+    pub fn dyn_symbol<'d>(&self, data: &'d [u8], index: usize) -> Option<&'d str> {
+        let class = self.headers.header.pt1.class();
+        let info = self.dynamic(data).ok()??;
+        let entsize = match (info.syment, class) {
+            (0, Class::ThirtyTwo) => 16,
+            (0, _) => 24,
+            (syment, _) => syment as usize,
+        };
+        let addr = info.symtab?.checked_add(index.checked_mul(entsize)?)?;
+        let entry = self.table(data, addr, entsize as u64).ok()?;
+        let sym = symbol_entries(entry, entsize, class).next()?;
+        let strtab = self.dynamic_strtab(data, &info).ok()?;
+        core::str::from_utf8(string_at(strtab, sym.name as usize)?).ok()
+    }
+
+    /// The sections of the ELF file.
+    ///
+    /// `data` is the content of the whole ELF file. The names are resolved
+    /// through the section header string table. The iterator is empty if
+    /// the file has no section headers, e.g. when stripped of them.
+    ///
+    /// This is meant for diagnostics: loading only uses program headers.
+    pub fn sections<'d>(&self, data: &'d [u8]) -> impl Iterator<Item = SectionInfo<'d>> + 'd {
+        let shstrtab = self
+            .raw_sections(data)
+            .nth(self.headers.header.pt2.sh_str_index() as usize)
+            .and_then(|s| s.data(data))
+            .unwrap_or(&[]);
+        self.raw_sections(data).map(move |s| SectionInfo {
+            name: string_at(shstrtab, s.name as usize)
+                .and_then(|name| core::str::from_utf8(name).ok())
+                .unwrap_or(""),
+            addr: s.addr as usize,
+            offset: s.offset as usize,
+            size: s.size as usize,
+            sh_type: s.sh_type,
+        })
+    }
+
+    /// Iterate over the section headers in `data`, the whole ELF file.
+    fn raw_sections<'d>(&self, data: &'d [u8]) -> impl Iterator<Item = RawSection> + 'd {
+        let pt2 = &self.headers.header.pt2;
+        section_headers(
+            data,
+            pt2.sh_offset(),
+            pt2.sh_count(),
+            pt2.sh_entry_size(),
+            self.headers.header.pt1.class(),
+        )
+    }
+
+    /// Translate the link-time virtual address range `[vaddr, vaddr + len)`
+    /// to its file offset, if it is backed by the file in a `LOAD` segment.
+    fn file_offset(&self, vaddr: u64, len: u64) -> Option<usize> {
+        file_offset_in(&self.headers.ph, vaddr, len)
+    }
+
     /// Part of auxiliary vectors from the ELF file.
     ///
     /// # Arguments
     ///
     /// * `pagesz` - The page size of the system
-    /// * `ldso_base` - The base address of the dynamic linker (if exists)
+    /// * `clktck` - The frequency at which `times()` increments, usually 100
+    /// * `ldso_base` - The base address of the dynamic linker (if exists),
+    ///   reported as `AT_BASE`
+    /// * `vdso_base` - The address of the ELF header of the mapped vDSO (if
+    ///   exists), reported as `AT_SYSINFO_EHDR`
+    /// * `cred` - The credentials of the process, reported as `AT_UID`,
+    ///   `AT_EUID`, `AT_GID`, `AT_EGID` and `AT_SECURE`
+    /// * `extra` - Entries provided by the caller, which are appended after
+    ///   the entries derived from the ELF file
+    ///
+    /// `AT_FLAGS` is always 0, as on Linux. `AT_NOTELF` is never emitted, as
+    /// the program is an ELF file.
+    ///
+    /// `self` must parse the main program, not the dynamic linker: `AT_PHDR`,
+    /// `AT_PHENT`, `AT_PHNUM` and `AT_ENTRY` always describe the main program,
+    /// even though execution starts in the dynamic linker when `ldso_base` is
+    /// `Some`. The dynamic linker finds its own headers through `AT_BASE`.
+    ///
+    /// The order is deterministic: the entries provided by the crate come
+    /// first, then `extra` in the given order, then the `AT_NULL` terminator,
+    /// so they can be written to the user stack as is. `AT_NULL` entries in
+    /// `extra` are dropped so as not to cut the vector short. Duplicate types
+    /// are kept: like glibc and musl, which scan the vector in order,
+    /// consumers see the last entry of each type, so an entry in `extra`
+    /// overrides the one provided by the crate.
+    ///
+    /// Returns [`ElfParseError::PhdrNotMapped`] if `AT_PHDR` can not be
+    /// computed, see [`Self::phdr`].
+    ///
+    /// Entries whose values live in user memory are passed through `extra`.
+    /// For example, `AT_RANDOM` must hold the address of 16 random bytes
+    /// already placed on the user stack, not the bytes themselves. Arch
+    /// specific values such as the `AT_HWCAP` and `AT_HWCAP2` bitmasks are
+    /// also left to the caller, as well as `AT_EXECFN`, which must be the
+    /// address of the NUL-terminated program path already written to user
+    /// memory. The same holds for `AT_PLATFORM`, whose string (e.g.
+    /// `"x86_64"`) is chosen by the kernel and omitted if not given, and for
+    /// `AT_MINSIGSTKSZ`, the size of a signal frame, which depends on the
+    /// vector state enabled on the CPU.
     ///
     /// Details about auxiliary vectors are described in <https://articles.manugarg.com/aboutelfauxiliaryvectors.html>
     pub fn aux_vector(
         &self,
         pagesz: usize,
+        clktck: usize,
         ldso_base: Option<usize>,
-    ) -> impl Iterator<Item = AuxEntry> {
-        [
-            (AuxType::PHDR, self.phdr()),
-            (AuxType::PHENT, self.phent()),
-            (AuxType::PHNUM, self.phnum()),
-            (AuxType::PAGESZ, pagesz),
-            (AuxType::ENTRY, self.entry()),
-        ]
-        .into_iter()
-        .chain(ldso_base.into_iter().map(|base| (AuxType::BASE, base)))
-        .map(|(at, val)| AuxEntry::new(at, val))
+        vdso_base: Option<usize>,
+        cred: Credentials,
+        extra: &[AuxEntry],
+    ) -> Result<impl Iterator<Item = AuxEntry>, ElfParseError> {
+        let mut builder = self
+            .aux_builder()
+            .page_size(pagesz)
+            .clktck(clktck)
+            .credentials(cred)
+            .extra(extra);
+        if let Some(base) = ldso_base {
+            builder = builder.interp_base(base);
+        }
+        if let Some(base) = vdso_base {
+            builder = builder.vdso_base(base);
+        }
+        builder.build()
+    }
+
+    /// Start building the auxiliary vector of the ELF file with an
+    /// [`AuxBuilder`], which names each value instead of taking them as
+    /// positional arguments like [`Self::aux_vector`].
+    pub fn aux_builder(&self) -> AuxBuilder<'static> {
+        AuxBuilder::new(self)
     }
 }
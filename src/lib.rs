@@ -1,10 +1,33 @@
 #![cfg_attr(not(test), no_std)]
 #![doc = include_str!("../README.md")]
 
+#[cfg(feature = "alloc")]
 extern crate alloc;
 
 mod auxv;
+mod dynamic;
+mod error;
 mod info;
+mod note;
+mod read;
+mod reloc;
+mod section;
+mod segment;
+mod symbol;
 mod user_stack;
 
-pub use self::{auxv::*, info::*, user_stack::app_stack_region};
+#[cfg(feature = "alloc")]
+pub use self::user_stack::app_stack_region;
+pub use self::{
+    auxv::*,
+    dynamic::DynamicInfo,
+    error::ElfParseError,
+    info::*,
+    note::{GnuProperties, Note},
+    read::ReadAt,
+    reloc::{RelocKind, Relocation},
+    section::SectionInfo,
+    segment::*,
+    user_stack::{StackError, align_stack_top, build_stack},
+};
+pub use xmas_elf::header::Machine;
@@ -0,0 +1,110 @@
+//! Notes parsed from `PT_NOTE` segments
+
+use core::iter;
+
+use xmas_elf::header::Machine;
+
+pub(crate) const NT_GNU_ABI_TAG: u32 = 1;
+pub(crate) const NT_GNU_BUILD_ID: u32 = 3;
+pub(crate) const NT_GNU_PROPERTY_TYPE_0: u32 = 5;
+
+const GNU_PROPERTY_AARCH64_FEATURE_1_AND: u32 = 0xc000_0000;
+const GNU_PROPERTY_X86_FEATURE_1_AND: u32 = 0xc000_0002;
+
+/// The OS of an `NT_GNU_ABI_TAG` note for Linux.
+pub(crate) const ELF_NOTE_OS_LINUX: u32 = 0;
+
+/// A note in a `PT_NOTE` segment.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Note<'a> {
+    /// The owner of the note (e.g. `"GNU"`), without the terminating NUL, or
+    /// an empty string if it is not UTF-8.
+    pub name: &'a str,
+    /// The type of the note, interpreted according to `name`.
+    pub kind: u32,
+    /// The descriptor of the note.
+    pub desc: &'a [u8],
+}
+
+/// Hardware control-flow integrity features supported by an ELF file, as
+/// declared in its `NT_GNU_PROPERTY_TYPE_0` notes.
+///
+/// A feature should only be enabled for a process if the executable and its
+/// interpreter both support it.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct GnuProperties {
+    /// x86 Indirect Branch Tracking (`GNU_PROPERTY_X86_FEATURE_1_IBT`)
+    pub ibt: bool,
+    /// x86 shadow stack (`GNU_PROPERTY_X86_FEATURE_1_SHSTK`)
+    pub shstk: bool,
+    /// AArch64 Branch Target Identification
+    /// (`GNU_PROPERTY_AARCH64_FEATURE_1_BTI`)
+    pub bti: bool,
+}
+
+impl GnuProperties {
+    /// Add the features of the property array `desc` of a
+    /// `NT_GNU_PROPERTY_TYPE_0` note.
+    ///
+    /// Each property is a `(type, size)` pair followed by its data, padded
+    /// to `align`: 8 for ELF64 and 4 for ELF32. The property types above
+    /// `0xc000_0000` are specific to the `machine`. Parsing stops at the
+    /// first truncated property.
+    pub(crate) fn add(&mut self, desc: &[u8], align: usize, machine: Machine) {
+        let mut rest = desc;
+        while rest.len() >= 8 {
+            let word = |at: usize| u32::from_ne_bytes(rest[at..at + 4].try_into().unwrap());
+            let (kind, size) = (word(0), word(4) as usize);
+            let Some(end) = size.checked_add(8).filter(|&end| end <= rest.len()) else {
+                return;
+            };
+            let bits = rest[8..end]
+                .get(..4)
+                .map_or(0, |bits| u32::from_ne_bytes(bits.try_into().unwrap()));
+            match (machine, kind) {
+                (Machine::X86 | Machine::X86_64, GNU_PROPERTY_X86_FEATURE_1_AND) => {
+                    self.ibt |= bits & 1 != 0;
+                    self.shstk |= bits & 2 != 0;
+                }
+                (Machine::AArch64, GNU_PROPERTY_AARCH64_FEATURE_1_AND) => {
+                    self.bti |= bits & 1 != 0;
+                }
+                _ => {}
+            }
+            rest = &rest[end.next_multiple_of(align).min(rest.len())..];
+        }
+    }
+}
+
+/// Iterate over the notes in the content of a `PT_NOTE` segment.
+///
+/// Both the name and the descriptor are padded to `align`, which is 4 for
+/// most notes and 8 for notes in segments aligned to 8 (e.g.
+/// `.note.gnu.property` of ELF64 files). Iteration stops at the first
+/// truncated note.
+pub(crate) fn note_entries(data: &[u8], align: usize) -> impl Iterator<Item = Note<'_>> {
+    let align = align.max(4);
+    let mut rest = data;
+    iter::from_fn(move || {
+        let word = |at: usize| u32::from_ne_bytes(rest[at..at + 4].try_into().unwrap()) as usize;
+        if rest.len() < 12 {
+            return None;
+        }
+        let (namesz, descsz, kind) = (word(0), word(4), word(8) as u32);
+        let desc_start = 12usize
+            .checked_add(namesz)?
+            .checked_next_multiple_of(align)?;
+        let desc_end = desc_start.checked_add(descsz)?;
+        if desc_end > rest.len() {
+            return None;
+        }
+        let name = &rest[12..12 + namesz];
+        let note = Note {
+            name: core::str::from_utf8(name.strip_suffix(b"\0").unwrap_or(name)).unwrap_or(""),
+            kind,
+            desc: &rest[desc_start..desc_end],
+        };
+        rest = &rest[desc_end.next_multiple_of(align).min(rest.len())..];
+        Some(note)
+    })
+}
@@ -0,0 +1,33 @@
+//! Reading the ELF file on demand
+
+use crate::error::ElfParseError;
+
+/// A source of the contents of the ELF file, read on demand.
+///
+/// This is for loaders that can't hold the whole file in memory, e.g. when
+/// it is read from a block device or decompressed lazily. The headers are
+/// read with [`ELFHeadersBuilder`](crate::ELFHeadersBuilder): the first 64
+/// bytes, then the program header table at
+/// [`ph_range`](crate::ELFHeadersBuilder::ph_range). The segments are then
+/// read with [`ELFParser::read_segment_at`](crate::ELFParser::read_segment_at)
+/// and [`ELFParser::read_notes`](crate::ELFParser::read_notes).
+///
+/// It is implemented for `[u8]`, the whole ELF file.
+pub trait ReadAt {
+    /// Fill `buf` with the bytes of the file at `offset`.
+    ///
+    /// Returns [`ElfParseError::Truncated`] if the range is out of the bounds
+    /// of the file.
+    fn read_at(&self, offset: usize, buf: &mut [u8]) -> Result<(), ElfParseError>;
+}
+
+impl ReadAt for [u8] {
+    fn read_at(&self, offset: usize, buf: &mut [u8]) -> Result<(), ElfParseError> {
+        let src = offset
+            .checked_add(buf.len())
+            .and_then(|end| self.get(offset..end))
+            .ok_or(ElfParseError::Truncated)?;
+        buf.copy_from_slice(src);
+        Ok(())
+    }
+}
@@ -0,0 +1,156 @@
+//! Relocations of the dynamic section
+
+use xmas_elf::header::{Class, Machine};
+
+/// A relocation entry read from the `DT_RELA` or `DT_JMPREL` table, or
+/// implied by the `DT_RELR` table.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Relocation {
+    /// The address to be patched, adjusted by the load base.
+    pub offset: usize,
+    /// The arch-specific relocation type (`R_*`).
+    pub kind: u32,
+    /// How the value of the relocation is computed, classified from `kind`.
+    pub category: RelocKind,
+    /// The index of the referenced symbol in the dynamic symbol table, or
+    /// `None` for relocations without a symbol (e.g. `R_*_RELATIVE`).
+    ///
+    /// The symbol is named by [`ELFParser::dyn_symbol`], and its address is
+    /// to be resolved by the caller, e.g. from the other loaded objects.
+    ///
+    /// [`ELFParser::dyn_symbol`]: crate::ELFParser::dyn_symbol
+    pub sym_index: Option<usize>,
+    /// The constant addend used to compute the value. For `DT_RELR`
+    /// relocations, this is the value stored at the address in the file.
+    pub addend: i64,
+}
+
+/// The classes of relocation types, as far as loading is concerned.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RelocKind {
+    /// `R_*_RELATIVE`: the value is `base + addend`.
+    Relative,
+    /// `R_*_IRELATIVE`: the value is returned by the IFUNC resolver at
+    /// `base + addend`.
+    IRelative,
+    /// Any other type, e.g. those referring to a symbol, or all types of a
+    /// machine whose relocation types are unknown.
+    Other,
+}
+
+/// The relocation types of a machine that need no symbol lookup.
+#[derive(Clone, Copy)]
+pub(crate) struct RelocTypes {
+    /// `R_*_RELATIVE`
+    pub relative: u32,
+    /// `R_*_IRELATIVE`
+    pub irelative: u32,
+}
+
+impl RelocTypes {
+    /// Classify the relocation type `kind`.
+    pub fn category(&self, kind: u32) -> RelocKind {
+        if kind == self.relative {
+            RelocKind::Relative
+        } else if kind == self.irelative {
+            RelocKind::IRelative
+        } else {
+            RelocKind::Other
+        }
+    }
+}
+
+/// The relocation types of the given machine.
+pub(crate) fn reloc_kinds_for(machine: Machine) -> Option<RelocTypes> {
+    let (relative, irelative) = match machine {
+        Machine::X86_64 => (8, 37),
+        Machine::AArch64 => (1027, 1032),
+        Machine::RISC_V => (3, 58),
+        // LoongArch
+        Machine::Other(258) => (3, 12),
+        _ => return None,
+    };
+    Some(RelocTypes {
+        relative,
+        irelative,
+    })
+}
+
+/// Iterate the relocations of a `Rela` table.
+///
+/// `entsize` is the size of each entry as declared by `DT_RELAENT`. An entry
+/// is `None` if its address overflows once adjusted by `base`.
+pub(crate) fn rela_entries(
+    table: &[u8],
+    entsize: usize,
+    class: Class,
+    base: usize,
+    types: Option<RelocTypes>,
+) -> impl Iterator<Item = Option<Relocation>> + Clone + '_ {
+    table.chunks_exact(entsize).map(move |entry| {
+        let (offset, kind, sym, addend) = match class {
+            Class::ThirtyTwo => {
+                let read = |i: usize| u32::from_ne_bytes(entry[i..i + 4].try_into().unwrap());
+                let info = read(4);
+                (
+                    read(0) as usize,
+                    info & 0xff,
+                    info >> 8,
+                    read(8) as i32 as i64,
+                )
+            }
+            _ => {
+                let read = |i: usize| u64::from_ne_bytes(entry[i..i + 8].try_into().unwrap());
+                let info = read(8);
+                (
+                    read(0) as usize,
+                    info as u32,
+                    (info >> 32) as u32,
+                    read(16) as i64,
+                )
+            }
+        };
+        Some(Relocation {
+            offset: offset.checked_add(base)?,
+            kind,
+            category: types.map_or(RelocKind::Other, |types| types.category(kind)),
+            // Symbol 0 is the undefined symbol `STN_UNDEF`.
+            sym_index: (sym != 0).then_some(sym as usize),
+            addend,
+        })
+    })
+}
+
+/// Iterate the link-time addresses encoded in a `Relr` table.
+///
+/// An even entry is an address to relocate, and starts a run of words. An
+/// odd entry is a bitmap of the run: bit `i` (from 1) set means the `i - 1`th
+/// word after the run's current position is to be relocated as well. Each
+/// bitmap then moves the run forward by `word bits - 1` words.
+pub(crate) fn relr_entries(table: &[u8], class: Class) -> impl Iterator<Item = u64> + '_ {
+    let word = match class {
+        Class::ThirtyTwo => 4,
+        _ => 8,
+    };
+    let read = move |entry: &[u8]| match word {
+        4 => u32::from_ne_bytes(entry.try_into().unwrap()) as u64,
+        _ => u64::from_ne_bytes(entry.try_into().unwrap()),
+    };
+    let bits = word as u64 * 8;
+    let mut next = 0u64;
+    table.chunks_exact(word).flat_map(move |entry| {
+        let entry = read(entry);
+        // The start of the run and the bitmap of the words from there.
+        let (start, bitmap) = if entry & 1 == 0 {
+            next = entry.wrapping_add(word as u64);
+            (entry, 1)
+        } else {
+            let start = next;
+            next = next.wrapping_add((bits - 1) * word as u64);
+            (start, entry >> 1)
+        };
+        (0..bits - 1)
+            .filter(move |i| bitmap >> i & 1 != 0)
+            .map(move |i| start.wrapping_add(i * word as u64))
+    })
+}
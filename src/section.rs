@@ -0,0 +1,105 @@
+//! Section headers parsed from the ELF file
+
+use xmas_elf::header::Class;
+
+pub(crate) const SHT_SYMTAB: u32 = 2;
+pub(crate) const SHT_DYNSYM: u32 = 11;
+
+/// A section described by a section header.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SectionInfo<'a> {
+    /// The name of the section, or an empty string if it cannot be resolved
+    /// through the section header string table.
+    pub name: &'a str,
+    /// The link-time virtual address of the section, not biased by the base
+    /// address. It is 0 for sections not loaded into the memory.
+    pub addr: usize,
+    /// The offset of the section in the ELF file.
+    pub offset: usize,
+    /// The size of the section in bytes. `SHT_NOBITS` sections (e.g. `.bss`)
+    /// take no space in the file.
+    pub size: usize,
+    /// The type of the section (`SHT_*`).
+    pub sh_type: u32,
+}
+
+/// The fields of a section header used by this crate.
+#[derive(Clone, Copy)]
+pub(crate) struct RawSection {
+    pub name: u32,
+    pub sh_type: u32,
+    pub addr: u64,
+    pub offset: u64,
+    pub size: u64,
+    pub link: u32,
+    pub entsize: u64,
+}
+
+impl RawSection {
+    /// The content of the section in `data`, the whole ELF file.
+    ///
+    /// Returns `None` if the section is out of the bounds of the file.
+    pub fn data<'d>(&self, data: &'d [u8]) -> Option<&'d [u8]> {
+        let start = self.offset as usize;
+        data.get(start..start.checked_add(self.size as usize)?)
+    }
+}
+
+/// Iterate over the section header table at `offset` in `data`.
+///
+/// The iterator is empty if the table is out of the bounds of the file or
+/// its entries are too small, e.g. for files stripped of section headers.
+pub(crate) fn section_headers(
+    data: &[u8],
+    offset: u64,
+    count: u16,
+    entsize: u16,
+    class: Class,
+) -> impl Iterator<Item = RawSection> + '_ {
+    let entsize = entsize as usize;
+    let min_entsize = match class {
+        Class::ThirtyTwo => 40,
+        _ => 64,
+    };
+    let table = (offset as usize)
+        .checked_add(count as usize * entsize)
+        .and_then(|end| data.get(offset as usize..end))
+        .filter(|_| entsize >= min_entsize)
+        .unwrap_or(&[]);
+    let u32_at =
+        |entry: &[u8], at: usize| u32::from_ne_bytes(entry[at..at + 4].try_into().unwrap());
+    let u64_at =
+        |entry: &[u8], at: usize| u64::from_ne_bytes(entry[at..at + 8].try_into().unwrap());
+    table
+        .chunks_exact(entsize.max(1))
+        .map(move |entry| match class {
+            Class::ThirtyTwo => RawSection {
+                name: u32_at(entry, 0),
+                sh_type: u32_at(entry, 4),
+                addr: u32_at(entry, 12) as u64,
+                offset: u32_at(entry, 16) as u64,
+                size: u32_at(entry, 20) as u64,
+                link: u32_at(entry, 24),
+                entsize: u32_at(entry, 36) as u64,
+            },
+            _ => RawSection {
+                name: u32_at(entry, 0),
+                sh_type: u32_at(entry, 4),
+                addr: u64_at(entry, 16),
+                offset: u64_at(entry, 24),
+                size: u64_at(entry, 32),
+                link: u32_at(entry, 40),
+                entsize: u64_at(entry, 56),
+            },
+        })
+}
+
+/// The NUL-terminated string at `offset` in the string table `strtab`.
+///
+/// Returns `None` if `offset` is out of bounds or the string is not
+/// terminated.
+pub(crate) fn string_at(strtab: &[u8], offset: usize) -> Option<&[u8]> {
+    let bytes = strtab.get(offset..)?;
+    let len = bytes.iter().position(|&b| b == 0)?;
+    Some(&bytes[..len])
+}
@@ -0,0 +1,294 @@
+//! Loadable segments of the ELF file
+
+use core::{
+    fmt,
+    ops::{BitAnd, BitOr, BitOrAssign, Range},
+};
+
+use xmas_elf::program::Flags;
+
+/// Permission flags used to map a segment into the page table.
+///
+/// The bit values follow the `MappingFlags` of ArceOS-style kernels.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct MappingFlags(usize);
+
+impl MappingFlags {
+    /// The memory is readable.
+    pub const READ: Self = Self(1 << 0);
+    /// The memory is writable.
+    pub const WRITE: Self = Self(1 << 1);
+    /// The memory is executable.
+    pub const EXECUTE: Self = Self(1 << 2);
+    /// The memory is user accessible.
+    pub const USER: Self = Self(1 << 3);
+
+    /// Create flags with no bit set.
+    pub const fn empty() -> Self {
+        Self(0)
+    }
+
+    /// The raw value of the flags.
+    pub const fn bits(&self) -> usize {
+        self.0
+    }
+
+    /// Whether all bits in `other` are set in `self`.
+    pub const fn contains(&self, other: Self) -> bool {
+        self.0 & other.0 == other.0
+    }
+}
+
+impl BitOr for MappingFlags {
+    type Output = Self;
+
+    fn bitor(self, rhs: Self) -> Self {
+        Self(self.0 | rhs.0)
+    }
+}
+
+impl BitOrAssign for MappingFlags {
+    fn bitor_assign(&mut self, rhs: Self) {
+        self.0 |= rhs.0;
+    }
+}
+
+impl BitAnd for MappingFlags {
+    type Output = Self;
+
+    fn bitand(self, rhs: Self) -> Self {
+        Self(self.0 & rhs.0)
+    }
+}
+
+impl From<Flags> for MappingFlags {
+    /// Translate the R/W/X bits of the program header flags, other bits are
+    /// dropped.
+    fn from(flags: Flags) -> Self {
+        let mut ret = Self::empty();
+        if flags.is_read() {
+            ret |= Self::READ;
+        }
+        if flags.is_write() {
+            ret |= Self::WRITE;
+        }
+        if flags.is_execute() {
+            ret |= Self::EXECUTE;
+        }
+        ret
+    }
+}
+
+/// ELF Program Header applied to the kernel
+#[derive(Clone, Copy)]
+pub struct ELFPH {
+    /// The type of the segment (`p_type`), e.g. 1 for `PT_LOAD`
+    pub kind: u32,
+    /// The start offset of the segment in the ELF file
+    pub offset: usize,
+    /// The destination virtual address of the segment in the kernel memory
+    pub vaddr: usize,
+    /// Memory size of the segment
+    pub memsz: u64,
+    /// File size of the segment
+    pub filesz: u64,
+    /// Flags of the segment as declared in the program header
+    pub flags: Flags,
+    /// Alignment of the segment in memory and in the file
+    pub align: u64,
+}
+
+impl fmt::Debug for ELFPH {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ELFPH")
+            .field("kind", &format_args!("{:#x}", self.kind))
+            .field("vaddr", &format_args!("{:#x}", self.vaddr))
+            .field("memsz", &format_args!("{:#x}", self.memsz))
+            .field("filesz", &format_args!("{:#x}", self.filesz))
+            .field("offset", &format_args!("{:#x}", self.offset))
+            .field("flags", &format_args!("{}", self.perm_str()))
+            .finish()
+    }
+}
+
+impl ELFPH {
+    /// Whether the segment is readable.
+    pub fn is_read(&self) -> bool {
+        self.flags.is_read()
+    }
+
+    /// Whether the segment is writable.
+    pub fn is_write(&self) -> bool {
+        self.flags.is_write()
+    }
+
+    /// Whether the segment is executable.
+    pub fn is_exec(&self) -> bool {
+        self.flags.is_execute()
+    }
+
+    /// The permissions of the segment like `r-x`, e.g. for logging.
+    pub fn perm_str(&self) -> &'static str {
+        match (self.is_read(), self.is_write(), self.is_exec()) {
+            (false, false, false) => "---",
+            (false, false, true) => "--x",
+            (false, true, false) => "-w-",
+            (false, true, true) => "-wx",
+            (true, false, false) => "r--",
+            (true, false, true) => "r-x",
+            (true, true, false) => "rw-",
+            (true, true, true) => "rwx",
+        }
+    }
+
+    /// [`MappingFlags`] of the segment which is used to set the page table
+    /// entry.
+    ///
+    /// The R/W/X bits are kept as is (so a write-only segment stays
+    /// write-only), and [`MappingFlags::USER`] is always set.
+    pub fn mapping_flags(&self) -> MappingFlags {
+        MappingFlags::from(self.flags) | MappingFlags::USER
+    }
+
+    /// Whether `offset` and `vaddr` are congruent modulo `align`, which the
+    /// ELF ABI requires to map the segment from the file.
+    ///
+    /// As `vaddr` includes the load base, this also tells whether the base
+    /// keeps the segment aligned, e.g. to map it with huge pages when `align`
+    /// is 2 MiB. Segments with an `align` of 0 or 1 are always congruent.
+    pub fn is_congruent(&self) -> bool {
+        self.align <= 1 || self.offset as u64 % self.align == self.vaddr as u64 % self.align
+    }
+
+    /// The zero-filled (BSS) part of the segment, i.e.
+    /// `[vaddr + filesz, vaddr + memsz)`.
+    ///
+    /// Returns `None` if the whole segment is backed by the file.
+    ///
+    /// The first page of the range is usually shared with the end of the
+    /// file-backed data. Only the tail of that page starting at
+    /// `vaddr + filesz` must be zeroed, and the file data before it must be
+    /// kept, so the page must not be mapped a second time as a fresh zero
+    /// page. The remaining pages of the range can be mapped as zero pages.
+    pub fn bss_range(&self) -> Option<Range<usize>> {
+        if self.memsz > self.filesz {
+            Some(self.vaddr + self.filesz as usize..self.vaddr + self.memsz as usize)
+        } else {
+            None
+        }
+    }
+}
+
+/// The fields of a segment needed to map it, independent of `xmas_elf`.
+///
+/// Kernels can write their mapping code against this trait, so that
+/// [`Flags`] stays out of their own APIs.
+///
+/// # Example
+///
+/// ```
+/// use kernel_elf_parser::{ELFParser, LoadSegment};
+///
+/// /// A memory area of the kernel.
+/// struct Area {
+///     start: usize,
+///     size: usize,
+///     writable: bool,
+/// }
+///
+/// fn areas(segments: impl Iterator<Item = impl LoadSegment>) -> Vec<Area> {
+///     segments
+///         .map(|seg| Area {
+///             start: seg.vaddr(),
+///             size: seg.memsz() as usize,
+///             writable: seg.is_write(),
+///         })
+///         .collect()
+/// }
+///
+/// fn load(parser: &ELFParser) -> Vec<Area> {
+///     areas(parser.ph_load())
+/// }
+/// ```
+pub trait LoadSegment {
+    /// The virtual address of the segment, including the load base
+    fn vaddr(&self) -> usize;
+    /// The offset of the segment in the ELF file
+    fn offset(&self) -> usize;
+    /// The number of bytes backed by the file
+    fn filesz(&self) -> u64;
+    /// The size of the segment in memory
+    fn memsz(&self) -> u64;
+    /// Whether the segment is readable
+    fn is_read(&self) -> bool;
+    /// Whether the segment is writable
+    fn is_write(&self) -> bool;
+    /// Whether the segment is executable
+    fn is_exec(&self) -> bool;
+}
+
+impl LoadSegment for ELFPH {
+    fn vaddr(&self) -> usize {
+        self.vaddr
+    }
+
+    fn offset(&self) -> usize {
+        self.offset
+    }
+
+    fn filesz(&self) -> u64 {
+        self.filesz
+    }
+
+    fn memsz(&self) -> u64 {
+        self.memsz
+    }
+
+    fn is_read(&self) -> bool {
+        ELFPH::is_read(self)
+    }
+
+    fn is_write(&self) -> bool {
+        ELFPH::is_write(self)
+    }
+
+    fn is_exec(&self) -> bool {
+        ELFPH::is_exec(self)
+    }
+}
+
+/// A page-aligned mapping of a `LOAD` segment.
+///
+/// Mapping `map_size` bytes of the file at `map_offset` to `map_vaddr` puts
+/// the segment data at `map_vaddr + first_page_pad`, as the ELF ABI requires
+/// the file offset and the virtual address of a segment to have the same
+/// remainder modulo the page size.
+///
+/// The regions of two adjacent segments may overlap on the page at their
+/// boundary, in which case the caller must not map that page twice.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MapRegion {
+    /// The virtual address of the segment rounded down to the page size
+    pub map_vaddr: usize,
+    /// The file offset of the segment rounded down to the page size
+    pub map_offset: usize,
+    /// The size of the mapping rounded up to the page size
+    pub map_size: usize,
+    /// [`MappingFlags`] of the segment
+    pub flags: MappingFlags,
+    /// The offset of the segment data in the first page
+    pub first_page_pad: usize,
+}
+
+impl MapRegion {
+    pub(crate) fn new(ph: &ELFPH, page_size: usize) -> Self {
+        let first_page_pad = ph.vaddr & (page_size - 1);
+        Self {
+            map_vaddr: ph.vaddr - first_page_pad,
+            map_offset: ph.offset & !(page_size - 1),
+            map_size: (first_page_pad + ph.memsz as usize + page_size - 1) & !(page_size - 1),
+            flags: ph.mapping_flags(),
+            first_page_pad,
+        }
+    }
+}
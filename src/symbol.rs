@@ -0,0 +1,49 @@
+//! Symbols parsed from the symbol tables
+
+use xmas_elf::header::Class;
+
+pub(crate) const SHN_UNDEF: u16 = 0;
+pub(crate) const SHN_ABS: u16 = 0xfff1;
+
+/// The fields of a symbol table entry used by this crate.
+#[derive(Clone, Copy)]
+pub(crate) struct RawSymbol {
+    pub name: u32,
+    pub value: u64,
+    pub shndx: u16,
+}
+
+/// Iterate over the entries of the symbol table `table`.
+///
+/// The iterator is empty if `entsize` is too small for the class.
+pub(crate) fn symbol_entries(
+    table: &[u8],
+    entsize: usize,
+    class: Class,
+) -> impl Iterator<Item = RawSymbol> + '_ {
+    let min_entsize = match class {
+        Class::ThirtyTwo => 16,
+        _ => 24,
+    };
+    let table = if entsize >= min_entsize { table } else { &[] };
+    let u16_at =
+        |entry: &[u8], at: usize| u16::from_ne_bytes(entry[at..at + 2].try_into().unwrap());
+    let u32_at =
+        |entry: &[u8], at: usize| u32::from_ne_bytes(entry[at..at + 4].try_into().unwrap());
+    let u64_at =
+        |entry: &[u8], at: usize| u64::from_ne_bytes(entry[at..at + 8].try_into().unwrap());
+    table
+        .chunks_exact(entsize.max(1))
+        .map(move |entry| match class {
+            Class::ThirtyTwo => RawSymbol {
+                name: u32_at(entry, 0),
+                value: u32_at(entry, 4) as u64,
+                shndx: u16_at(entry, 14),
+            },
+            _ => RawSymbol {
+                name: u32_at(entry, 0),
+                value: u64_at(entry, 8),
+                shndx: u16_at(entry, 6),
+            },
+        })
+}
@@ -27,6 +27,7 @@
 //!
 //! More details can be found in the link: <https://articles.manugarg.com/aboutelfauxiliaryvectors.html>
 
+#[cfg(feature = "alloc")]
 use alloc::{collections::VecDeque, string::String, vec::Vec};
 
 use zerocopy::IntoBytes;
@@ -49,6 +50,7 @@ use crate::auxv::{AuxEntry, AuxType};
 /// # Notes
 ///
 /// The detailed format is described in <https://articles.manugarg.com/aboutelfauxiliaryvectors.html>
+#[cfg(feature = "alloc")]
 pub fn app_stack_region(args: &[String], envs: &[String], auxv: &[AuxEntry], sp: usize) -> Vec<u8> {
     let mut data = VecDeque::new();
     let mut push = |src: &[u8]| -> usize {
@@ -131,3 +133,142 @@ pub fn app_stack_region(args: &[String], envs: &[String], auxv: &[AuxEntry], sp:
     result.extend_from_slice(second);
     result
 }
+
+/// Errors that can occur when building the initial user stack.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StackError {
+    /// The given buffer can not hold the whole initial stack frame.
+    BufferTooSmall,
+}
+
+impl core::fmt::Display for StackError {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            StackError::BufferTooSmall => write!(f, "buffer too small for the initial stack"),
+        }
+    }
+}
+
+impl core::error::Error for StackError {}
+
+/// Align the address below which the initial stack frame is pushed
+///
+/// # Arguments
+///
+/// * `sp`   - The address right below the argument and environment strings
+/// * `argc` - The number of arguments
+/// * `envc` - The number of environment variables
+/// * `auxc` - The number of auxiliary vectors, including `AT_NULL`
+///
+/// # Return
+///
+/// * `Some(top)` - The highest address not above `sp` from which `argc`, the
+///   `argv` and `envp` arrays with their NULL terminators and the auxiliary
+///   vectors can be pushed, so that the final stack pointer (pointing at
+///   `argc`) is 16-byte aligned as required by the x86_64, aarch64 and
+///   riscv64 ABIs
+/// * `None` - The stack frame doesn't fit below `sp`
+pub fn align_stack_top(sp: usize, argc: usize, envc: usize, auxc: usize) -> Option<usize> {
+    let words = argc
+        .checked_add(envc)?
+        .checked_add(auxc.checked_mul(2)?)?
+        .checked_add(3)?;
+    let size = words.checked_mul(core::mem::size_of::<usize>())?;
+    Some((sp.checked_sub(size)? & !0xf) + size)
+}
+
+/// Write the initial stack frame for user stack into a caller-provided buffer
+///
+/// # Arguments
+///
+/// * `argv`      - Arguments of the application
+/// * `envp`      - Environment variables of the application
+/// * `auxv`      - Auxiliary vectors of the application
+/// * `stack_top` - Highest address of the stack
+/// * `buf`       - Memory right below `stack_top`, i.e. `buf[buf.len() - 1]` is
+///   the byte at `stack_top - 1`
+///
+/// # Return
+///
+/// * `Ok(sp)` - The initial stack pointer, which is 16-byte aligned and points
+///   at `argc`
+/// * `Err(StackError::BufferTooSmall)` - `buf` can not hold the stack frame
+///
+/// # Notes
+///
+/// The argument and environment strings are copied to the top of the stack
+/// as raw bytes, so they need not be UTF-8 (e.g. file names), and each is
+/// NUL-terminated. The pointer arrays below them refer to their addresses
+/// relative to `stack_top`. If `auxv` has no `AT_EXECFN` entry, one pointing
+/// at the copy of `argv[0]` is added. A terminating `AT_NULL` entry is
+/// appended to `auxv` unless it already ends with one.
+pub fn build_stack(
+    argv: &[&[u8]],
+    envp: &[&[u8]],
+    auxv: &[AuxEntry],
+    stack_top: usize,
+    buf: &mut [u8],
+) -> Result<usize, StackError> {
+    const WORD: usize = core::mem::size_of::<usize>();
+    let auxv = match auxv.split_last() {
+        Some((last, rest)) if last.get_type() == AuxType::NULL => rest,
+        _ => auxv,
+    };
+    let add_execfn = !argv.is_empty() && auxv.iter().all(|e| e.get_type() != AuxType::EXECFN);
+    // The entries from `auxv`, the optional `AT_EXECFN` and `AT_NULL`.
+    let auxc = auxv.len() + add_execfn as usize + 1;
+
+    let strings_size: usize = argv.iter().chain(envp).map(|s| s.len() + 1).sum();
+    let words = 1 + (argv.len() + 1) + (envp.len() + 1) + auxc * 2;
+    let strings = stack_top
+        .checked_sub(strings_size)
+        .filter(|&pos| pos >= words * WORD + 0xf)
+        .ok_or(StackError::BufferTooSmall)?;
+    let sp = align_stack_top(strings, argv.len(), envp.len(), auxc)
+        .ok_or(StackError::BufferTooSmall)?
+        - words * WORD;
+    let size = stack_top - sp;
+    if size > buf.len() {
+        return Err(StackError::BufferTooSmall);
+    }
+    // Only the top `size` bytes of the buffer are used, starting at `sp`.
+    let offset = buf.len() - size;
+    let buf = &mut buf[offset..];
+    let mut pos = 0;
+    let mut write = |src: &[u8]| {
+        buf[pos..pos + src.len()].copy_from_slice(src);
+        pos += src.len();
+    };
+
+    // Write argc, the pointer arrays and the auxiliary vectors upwards from sp.
+    // The strings are packed at the top of the stack in the same order.
+    let mut str_addr = stack_top - strings_size;
+    write(argv.len().as_bytes());
+    for arg in argv {
+        write(str_addr.as_bytes());
+        str_addr += arg.len() + 1;
+    }
+    write(0usize.as_bytes());
+    for env in envp {
+        write(str_addr.as_bytes());
+        str_addr += env.len() + 1;
+    }
+    write(0usize.as_bytes());
+    write(auxv.as_bytes());
+    if add_execfn {
+        write(AuxEntry::new(AuxType::EXECFN, stack_top - strings_size).as_bytes());
+    }
+    write(AuxEntry::new(AuxType::NULL, 0).as_bytes());
+
+    // Clear the alignment padding, then copy the strings.
+    let strings_start = size - strings_size;
+    buf[pos..strings_start].fill(0);
+    pos = strings_start;
+    for s in argv.iter().chain(envp) {
+        buf[pos..pos + s.len()].copy_from_slice(s);
+        buf[pos + s.len()] = 0;
+        pos += s.len() + 1;
+    }
+
+    Ok(sp)
+}
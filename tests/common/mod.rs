@@ -0,0 +1,95 @@
+//! Helpers shared by the integration tests.
+#![allow(dead_code)]
+
+use kernel_elf_parser::ELFHeaders;
+
+pub const ET_EXEC: u16 = 2;
+pub const ET_DYN: u16 = 3;
+
+pub const PT_LOAD: u32 = 1;
+pub const PT_DYNAMIC: u32 = 2;
+pub const PT_NOTE: u32 = 4;
+pub const PT_PHDR: u32 = 6;
+pub const PT_TLS: u32 = 7;
+pub const PT_GNU_STACK: u32 = 0x6474_e551;
+pub const PT_GNU_PROPERTY: u32 = 0x6474_e553;
+
+pub const PF_X: u32 = 1;
+pub const PF_W: u32 = 2;
+pub const PF_R: u32 = 4;
+
+/// Copy the ELF file into a buffer suitable for parsing.
+pub fn load(elf_bytes: &[u8]) -> Vec<u8> {
+    let mut data = elf_bytes.to_vec();
+    if !data.len().is_multiple_of(16) {
+        data.resize(data.len() + 16 - data.len() % 16, 0);
+    }
+    data
+}
+
+/// Parse the ELF header and the program header table of `data`.
+pub fn headers(data: &[u8]) -> ELFHeaders<'_> {
+    ELFHeaders::from_bytes(data).expect("Failed to read elf headers")
+}
+
+/// A program header of a hand-crafted ELF file.
+#[derive(Clone, Copy)]
+pub struct Ph {
+    pub type_: u32,
+    pub flags: u32,
+    pub offset: u64,
+    pub vaddr: u64,
+    pub filesz: u64,
+    pub memsz: u64,
+    pub align: u64,
+}
+
+impl Ph {
+    pub fn new(type_: u32, flags: u32, offset: u64, vaddr: u64, filesz: u64, memsz: u64) -> Self {
+        Self {
+            type_,
+            flags,
+            offset,
+            vaddr,
+            filesz,
+            memsz,
+            align: 0x1000,
+        }
+    }
+
+    pub fn load(flags: u32, offset: u64, vaddr: u64, filesz: u64, memsz: u64) -> Self {
+        Self::new(PT_LOAD, flags, offset, vaddr, filesz, memsz)
+    }
+}
+
+/// Build a minimal x86_64 ELF64 file with the program header table placed
+/// right after the ELF header.
+pub fn elf64(e_type: u16, entry: u64, phs: &[Ph]) -> Vec<u8> {
+    let mut data = Vec::new();
+    data.extend_from_slice(&[0x7f, b'E', b'L', b'F', 2, 1, 1, 0]);
+    data.extend_from_slice(&[0; 8]);
+    data.extend_from_slice(&e_type.to_le_bytes());
+    data.extend_from_slice(&0x3eu16.to_le_bytes());
+    data.extend_from_slice(&1u32.to_le_bytes());
+    data.extend_from_slice(&entry.to_le_bytes());
+    data.extend_from_slice(&64u64.to_le_bytes());
+    data.extend_from_slice(&0u64.to_le_bytes());
+    data.extend_from_slice(&0u32.to_le_bytes());
+    data.extend_from_slice(&64u16.to_le_bytes());
+    data.extend_from_slice(&56u16.to_le_bytes());
+    data.extend_from_slice(&(phs.len() as u16).to_le_bytes());
+    data.extend_from_slice(&64u16.to_le_bytes());
+    data.extend_from_slice(&0u16.to_le_bytes());
+    data.extend_from_slice(&0u16.to_le_bytes());
+    for ph in phs {
+        data.extend_from_slice(&ph.type_.to_le_bytes());
+        data.extend_from_slice(&ph.flags.to_le_bytes());
+        data.extend_from_slice(&ph.offset.to_le_bytes());
+        data.extend_from_slice(&ph.vaddr.to_le_bytes());
+        data.extend_from_slice(&ph.vaddr.to_le_bytes());
+        data.extend_from_slice(&ph.filesz.to_le_bytes());
+        data.extend_from_slice(&ph.memsz.to_le_bytes());
+        data.extend_from_slice(&ph.align.to_le_bytes());
+    }
+    data
+}
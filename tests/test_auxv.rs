@@ -0,0 +1,428 @@
+mod common;
+
+use common::{ET_DYN, PF_R, PF_X, Ph};
+use kernel_elf_parser::{AuxEntry, AuxType, Credentials, ELFParser};
+
+fn simple_elf() -> Vec<u8> {
+    common::elf64(
+        ET_DYN,
+        0x1000,
+        &[Ph::load(PF_R | PF_X, 0, 0, 0x2000, 0x2000)],
+    )
+}
+
+fn find(auxv: &[AuxEntry], at: AuxType) -> Option<usize> {
+    auxv.iter().find(|e| e.get_type() == at).map(|e| e.value())
+}
+
+#[test]
+fn test_random() {
+    let data = simple_elf();
+    let headers = common::headers(&data);
+    let parser = ELFParser::new(&headers, 0x1000).unwrap();
+    let random_addr = 0x3fff_fff0;
+    let auxv = parser
+        .aux_vector(
+            0x1000,
+            100,
+            None,
+            None,
+            Credentials::default(),
+            &[AuxEntry::new(AuxType::RANDOM, random_addr)],
+        )
+        .unwrap()
+        .collect::<Vec<_>>();
+    assert_eq!(find(&auxv, AuxType::RANDOM), Some(random_addr));
+    assert_eq!(find(&auxv, AuxType::ENTRY), Some(0x2000));
+    assert!(auxv[auxv.len() - 2].get_type() == AuxType::RANDOM);
+}
+
+#[test]
+fn test_hwcap() {
+    let data = simple_elf();
+    let headers = common::headers(&data);
+    let parser = ELFParser::new(&headers, 0).unwrap();
+    let extra = [
+        AuxEntry::new(AuxType::HWCAP, 0x112d),
+        AuxEntry::new(AuxType::HWCAP2, 0x2),
+    ];
+    let auxv = parser
+        .aux_vector(0x1000, 100, None, None, Credentials::default(), &extra)
+        .unwrap()
+        .collect::<Vec<_>>();
+    assert_eq!(find(&auxv, AuxType::HWCAP), Some(0x112d));
+    assert_eq!(find(&auxv, AuxType::HWCAP2), Some(0x2));
+    // Caller entries keep their order after the ones from the ELF file.
+    let n = auxv.len() - 1;
+    assert!(auxv[n - 2].get_type() == AuxType::HWCAP);
+    assert!(auxv[n - 1].get_type() == AuxType::HWCAP2);
+}
+
+#[test]
+fn test_credentials() {
+    let data = simple_elf();
+    let headers = common::headers(&data);
+    let parser = ELFParser::new(&headers, 0).unwrap();
+    let cred = Credentials {
+        uid: 1000,
+        euid: 1000,
+        gid: 100,
+        egid: 100,
+    };
+    let auxv = parser
+        .aux_vector(0x1000, 100, None, None, cred, &[])
+        .unwrap()
+        .collect::<Vec<_>>();
+    let types = auxv
+        .iter()
+        .map(|e| e.get_type() as usize)
+        .collect::<Vec<_>>();
+    let at = types
+        .iter()
+        .position(|&t| t == AuxType::UID as usize)
+        .unwrap();
+    assert_eq!(
+        types[at..at + 5],
+        [
+            AuxType::UID as usize,
+            AuxType::EUID as usize,
+            AuxType::GID as usize,
+            AuxType::EGID as usize,
+            AuxType::SECURE as usize,
+        ]
+    );
+    assert_eq!(find(&auxv, AuxType::UID), Some(1000));
+    assert_eq!(find(&auxv, AuxType::EGID), Some(100));
+}
+
+#[test]
+fn test_secure() {
+    let data = simple_elf();
+    let headers = common::headers(&data);
+    let parser = ELFParser::new(&headers, 0).unwrap();
+    for (euid, secure) in [(1000, false), (0, true)] {
+        let cred = Credentials {
+            uid: 1000,
+            euid,
+            ..Default::default()
+        };
+        let auxv = parser
+            .aux_vector(0x1000, 100, None, None, cred, &[])
+            .unwrap()
+            .collect::<Vec<_>>();
+        assert_eq!(find(&auxv, AuxType::SECURE), Some(secure as usize));
+    }
+}
+
+#[test]
+fn test_clktck() {
+    let data = simple_elf();
+    let headers = common::headers(&data);
+    let parser = ELFParser::new(&headers, 0).unwrap();
+    let auxv = parser
+        .aux_vector(0x1000, 250, None, None, Credentials::default(), &[])
+        .unwrap()
+        .collect::<Vec<_>>();
+    assert_eq!(find(&auxv, AuxType::CLKTCK), Some(250));
+}
+
+#[test]
+fn test_platform() {
+    let data = simple_elf();
+    let headers = common::headers(&data);
+    let parser = ELFParser::new(&headers, 0).unwrap();
+    for platform in [None, Some(0x3fff_ffe0)] {
+        let extra = platform
+            .map(|addr| AuxEntry::new(AuxType::PLATFORM, addr))
+            .into_iter()
+            .collect::<Vec<_>>();
+        let auxv = parser
+            .aux_vector(0x1000, 100, None, None, Credentials::default(), &extra)
+            .unwrap()
+            .collect::<Vec<_>>();
+        assert_eq!(find(&auxv, AuxType::PLATFORM), platform);
+    }
+}
+
+#[test]
+fn test_base_platform() {
+    let data = simple_elf();
+    let headers = common::headers(&data);
+    let parser = ELFParser::new(&headers, 0).unwrap();
+    let auxv = parser.aux_builder().build().unwrap().collect::<Vec<_>>();
+    assert_eq!(find(&auxv, AuxType::BASE_PLATFORM), None);
+
+    let auxv = parser
+        .aux_builder()
+        .platform(0x3fff_ffe0)
+        .base_platform(0x3fff_ffd0)
+        .build()
+        .unwrap()
+        .collect::<Vec<_>>();
+    let tail = auxv[auxv.len() - 3..]
+        .iter()
+        .map(|e| (e.get_type() as usize, e.value()))
+        .collect::<Vec<_>>();
+    assert_eq!(
+        tail,
+        [
+            (AuxType::PLATFORM as usize, 0x3fff_ffe0),
+            (AuxType::BASE_PLATFORM as usize, 0x3fff_ffd0),
+            (AuxType::NULL as usize, 0),
+        ]
+    );
+}
+
+#[test]
+fn test_terminator() {
+    let data = simple_elf();
+    let headers = common::headers(&data);
+    let parser = ELFParser::new(&headers, 0).unwrap();
+    let auxv = parser
+        .aux_vector(0x1000, 100, None, None, Credentials::default(), &[])
+        .unwrap()
+        .collect::<Vec<_>>();
+    // PHDR, PHENT, PHNUM, PAGESZ, CLKTCK, ENTRY, FLAGS, the credentials and
+    // AT_NULL.
+    assert_eq!(auxv.len(), 13);
+    assert_eq!(find(&auxv, AuxType::FLAGS), Some(0));
+    assert_eq!(find(&auxv, AuxType::NOTELF), None);
+    let last = auxv.last().unwrap();
+    assert!(last.get_type() == AuxType::NULL);
+    assert_eq!(last.value(), 0);
+    assert_eq!(
+        auxv.iter()
+            .filter(|e| e.get_type() == AuxType::NULL)
+            .count(),
+        1
+    );
+}
+
+#[test]
+fn test_vdso() {
+    let data = simple_elf();
+    let headers = common::headers(&data);
+    let parser = ELFParser::new(&headers, 0).unwrap();
+    let auxv = parser
+        .aux_vector(0x1000, 100, None, None, Credentials::default(), &[])
+        .unwrap()
+        .collect::<Vec<_>>();
+    assert_eq!(find(&auxv, AuxType::SYSINFO_EHDR), None);
+    let auxv = parser
+        .aux_vector(
+            0x1000,
+            100,
+            None,
+            Some(0x7fff_0000),
+            Credentials::default(),
+            &[],
+        )
+        .unwrap()
+        .collect::<Vec<_>>();
+    assert_eq!(find(&auxv, AuxType::SYSINFO_EHDR), Some(0x7fff_0000));
+}
+
+#[test]
+fn test_extra_order() {
+    let data = simple_elf();
+    let headers = common::headers(&data);
+    let parser = ELFParser::new(&headers, 0).unwrap();
+    let auxv = parser
+        .aux_vector(
+            0x1000,
+            100,
+            None,
+            None,
+            Credentials::default(),
+            &[
+                AuxEntry::new(AuxType::PAGESZ, 0x4000),
+                AuxEntry::new(AuxType::NULL, 0),
+                AuxEntry::new(AuxType::HWCAP, 0xff),
+            ],
+        )
+        .unwrap()
+        .collect::<Vec<_>>();
+    // The crate-provided entries, then the extras without AT_NULL, then
+    // AT_NULL.
+    assert_eq!(auxv.len(), 15);
+    assert!(auxv[12].get_type() == AuxType::PAGESZ);
+    assert!(auxv[13].get_type() == AuxType::HWCAP);
+    assert!(auxv[14].get_type() == AuxType::NULL);
+    // The last AT_PAGESZ wins.
+    let pagesz = auxv
+        .iter()
+        .rev()
+        .find(|e| e.get_type() == AuxType::PAGESZ)
+        .map(|e| e.value());
+    assert_eq!(find(&auxv, AuxType::PAGESZ), Some(0x1000));
+    assert_eq!(pagesz, Some(0x4000));
+}
+
+#[test]
+fn test_minsigstksz() {
+    let data = simple_elf();
+    let headers = common::headers(&data);
+    let parser = ELFParser::new(&headers, 0).unwrap();
+    // e.g. the signal frame size with AVX-512 state on x86_64.
+    let auxv = parser
+        .aux_vector(
+            0x1000,
+            100,
+            None,
+            None,
+            Credentials::default(),
+            &[AuxEntry::new(AuxType::MINSIGSTKSZ, 0xd30)],
+        )
+        .unwrap()
+        .collect::<Vec<_>>();
+    assert_eq!(find(&auxv, AuxType::MINSIGSTKSZ), Some(0xd30));
+    assert_eq!(AuxType::MINSIGSTKSZ as usize, 51);
+}
+
+#[test]
+fn test_builder() {
+    let data = simple_elf();
+    let headers = common::headers(&data);
+    let parser = ELFParser::new(&headers, 0x1000).unwrap();
+    let extra = [AuxEntry::new(AuxType::MINSIGSTKSZ, 0xd30)];
+    let cred = Credentials {
+        uid: 1000,
+        euid: 0,
+        gid: 100,
+        egid: 100,
+    };
+    let auxv = parser
+        .aux_builder()
+        .page_size(0x4000)
+        .clktck(250)
+        .interp_base(0x7fff_0000)
+        .vdso_base(0x7ffe_0000)
+        .credentials(cred)
+        .hwcap(0x112d)
+        .hwcap2(0x2)
+        .random(0x3fff_ffe0)
+        .execfn(0x3fff_fff0)
+        .platform(0x3fff_ffd0)
+        .base_platform(0x3fff_ffc0)
+        .extra(&extra)
+        .build()
+        .unwrap()
+        .map(|e| (e.get_type() as usize, e.value()))
+        .collect::<Vec<_>>();
+    let expected = [
+        (AuxType::SYSINFO_EHDR, 0x7ffe_0000),
+        (AuxType::PHDR, 0x1040),
+        (AuxType::PHENT, 56),
+        (AuxType::PHNUM, 1),
+        (AuxType::PAGESZ, 0x4000),
+        (AuxType::CLKTCK, 250),
+        (AuxType::ENTRY, 0x2000),
+        (AuxType::FLAGS, 0),
+        (AuxType::BASE, 0x7fff_0000),
+        (AuxType::UID, 1000),
+        (AuxType::EUID, 0),
+        (AuxType::GID, 100),
+        (AuxType::EGID, 100),
+        (AuxType::SECURE, 1),
+        (AuxType::HWCAP, 0x112d),
+        (AuxType::HWCAP2, 0x2),
+        (AuxType::RANDOM, 0x3fff_ffe0),
+        (AuxType::EXECFN, 0x3fff_fff0),
+        (AuxType::PLATFORM, 0x3fff_ffd0),
+        (AuxType::BASE_PLATFORM, 0x3fff_ffc0),
+        (AuxType::MINSIGSTKSZ, 0xd30),
+        (AuxType::NULL, 0),
+    ]
+    .map(|(at, val)| (at as usize, val));
+    assert_eq!(auxv, expected);
+
+    // `aux_vector` is the same builder with positional arguments.
+    let positional = parser
+        .aux_vector(0x1000, 100, Some(0x7fff_0000), None, cred, &extra)
+        .unwrap()
+        .map(|e| (e.get_type() as usize, e.value()))
+        .collect::<Vec<_>>();
+    let built = parser
+        .aux_builder()
+        .interp_base(0x7fff_0000)
+        .credentials(cred)
+        .extra(&extra)
+        .build()
+        .unwrap()
+        .map(|e| (e.get_type() as usize, e.value()))
+        .collect::<Vec<_>>();
+    assert_eq!(positional, built);
+}
+
+/// The numbers and names of `include/uapi/linux/auxvec.h`.
+const AUX_TYPES: [(AuxType, usize, &str); 43] = [
+    (AuxType::NULL, 0, "AT_NULL"),
+    (AuxType::IGNORE, 1, "AT_IGNORE"),
+    (AuxType::EXECFD, 2, "AT_EXECFD"),
+    (AuxType::PHDR, 3, "AT_PHDR"),
+    (AuxType::PHENT, 4, "AT_PHENT"),
+    (AuxType::PHNUM, 5, "AT_PHNUM"),
+    (AuxType::PAGESZ, 6, "AT_PAGESZ"),
+    (AuxType::BASE, 7, "AT_BASE"),
+    (AuxType::FLAGS, 8, "AT_FLAGS"),
+    (AuxType::ENTRY, 9, "AT_ENTRY"),
+    (AuxType::NOTELF, 10, "AT_NOTELF"),
+    (AuxType::UID, 11, "AT_UID"),
+    (AuxType::EUID, 12, "AT_EUID"),
+    (AuxType::GID, 13, "AT_GID"),
+    (AuxType::EGID, 14, "AT_EGID"),
+    (AuxType::PLATFORM, 15, "AT_PLATFORM"),
+    (AuxType::HWCAP, 16, "AT_HWCAP"),
+    (AuxType::CLKTCK, 17, "AT_CLKTCK"),
+    (AuxType::FPUCW, 18, "AT_FPUCW"),
+    (AuxType::DCACHEBSIZE, 19, "AT_DCACHEBSIZE"),
+    (AuxType::ICACHEBSIZE, 20, "AT_ICACHEBSIZE"),
+    (AuxType::UCACHEBSIZE, 21, "AT_UCACHEBSIZE"),
+    (AuxType::IGNOREPPC, 22, "AT_IGNOREPPC"),
+    (AuxType::SECURE, 23, "AT_SECURE"),
+    (AuxType::BASE_PLATFORM, 24, "AT_BASE_PLATFORM"),
+    (AuxType::RANDOM, 25, "AT_RANDOM"),
+    (AuxType::HWCAP2, 26, "AT_HWCAP2"),
+    (AuxType::EXECFN, 31, "AT_EXECFN"),
+    (AuxType::SYSINFO, 32, "AT_SYSINFO"),
+    (AuxType::SYSINFO_EHDR, 33, "AT_SYSINFO_EHDR"),
+    (AuxType::L1I_CACHESHAPE, 34, "AT_L1I_CACHESHAPE"),
+    (AuxType::L1D_CACHESHAPE, 35, "AT_L1D_CACHESHAPE"),
+    (AuxType::L2_CACHESHAPE, 36, "AT_L2_CACHESHAPE"),
+    (AuxType::L3_CACHESHAPE, 37, "AT_L3_CACHESHAPE"),
+    (AuxType::L1I_CACHESIZE, 40, "AT_L1I_CACHESIZE"),
+    (AuxType::L1I_CACHEGEOMETRY, 41, "AT_L1I_CACHEGEOMETRY"),
+    (AuxType::L1D_CACHESIZE, 42, "AT_L1D_CACHESIZE"),
+    (AuxType::L1D_CACHEGEOMETRY, 43, "AT_L1D_CACHEGEOMETRY"),
+    (AuxType::L2_CACHESIZE, 44, "AT_L2_CACHESIZE"),
+    (AuxType::L2_CACHEGEOMETRY, 45, "AT_L2_CACHEGEOMETRY"),
+    (AuxType::L3_CACHESIZE, 46, "AT_L3_CACHESIZE"),
+    (AuxType::L3_CACHEGEOMETRY, 47, "AT_L3_CACHEGEOMETRY"),
+    (AuxType::MINSIGSTKSZ, 51, "AT_MINSIGSTKSZ"),
+];
+
+#[test]
+fn test_key() {
+    for (at, number, _) in AUX_TYPES {
+        let entry = AuxEntry::new(at, 0x1234);
+        assert_eq!(entry.key(), number);
+        assert_eq!(entry.to_pair(), [number, 0x1234]);
+    }
+}
+
+#[test]
+fn test_display() {
+    for (at, _, name) in AUX_TYPES {
+        assert_eq!(at.to_string(), name);
+        assert_eq!(format!("{at:?}"), name);
+    }
+    assert_eq!(
+        format!("{:?}", AuxEntry::new(AuxType::PHDR, 0x40_0040)),
+        "AT_PHDR => 0x400040"
+    );
+    let auxv = [
+        AuxEntry::new(AuxType::PAGESZ, 0x1000),
+        AuxEntry::new(AuxType::NULL, 0),
+    ];
+    assert_eq!(format!("{auxv:?}"), "[AT_PAGESZ => 0x1000, AT_NULL => 0x0]");
+}
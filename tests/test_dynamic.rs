@@ -1,27 +1,559 @@
+use std::cell::Cell;
+
+use kernel_elf_parser::{
+    AuxType, Credentials, DynamicInfo, ELFHeadersBuilder, ELFParser, ElfParseError, GnuProperties,
+    ReadAt, RelocKind,
+};
+
+mod common;
+
 #[test]
 fn test_elf_parser() {
-    let elf_bytes = include_bytes!("ld-linux-x86-64.so.2");
-    // Ensure the alignment of the byte array
-    let mut aligned_elf_bytes = unsafe {
-        let ptr = elf_bytes.as_ptr() as *mut u8;
-        std::slice::from_raw_parts_mut(ptr, elf_bytes.len())
-    }
-    .to_vec();
-    if aligned_elf_bytes.len() % 16 != 0 {
-        let padding = vec![0u8; 16 - aligned_elf_bytes.len() % 16];
-        aligned_elf_bytes.extend(padding);
-    }
-    let elf =
-        xmas_elf::ElfFile::new(aligned_elf_bytes.as_slice()).expect("Failed to read elf file");
+    let elf_bytes = common::load(include_bytes!("ld-linux-x86-64.so.2"));
+    let headers = common::headers(&elf_bytes);
     let interp_base = 0x1000;
-    let elf_parser = kernel_elf_parser::ELFParser::new(&elf, interp_base).unwrap();
+    let elf_parser = kernel_elf_parser::ELFParser::new(&headers, interp_base).unwrap();
     let base_addr = elf_parser.base();
     assert_eq!(base_addr, interp_base);
+    // Like a static PIE: relocates itself and has no interpreter.
+    assert!(elf_parser.is_pie());
+    assert!(elf_parser.is_dynamic());
+    assert!(!elf_parser.is_static());
+    assert!(!elf_parser.needs_interp());
+    assert!(elf_parser.is_static_pie());
+    assert_eq!(elf_parser.os_abi(), 3);
 
     let segments = elf_parser.ph_load().collect::<Vec<_>>();
     assert_eq!(segments.len(), 4);
+    assert_eq!(elf_parser.load_count(), 4);
     for segment in segments.iter() {
         println!("{:?} {:?}", segment.vaddr, segment.flags);
     }
     assert_eq!(segments[0].vaddr, 0x1000);
+    assert!(segments.iter().all(|ph| ph.kind == 1));
+
+    assert_eq!(elf_parser.phnum(), 11);
+    let dynamic = elf_parser.program_header(4).unwrap();
+    assert_eq!(dynamic.kind, 2);
+    assert_eq!(dynamic.vaddr, 0x39e80 + interp_base);
+    let relro = elf_parser.program_header(10).unwrap();
+    assert_eq!(relro.kind, 0x6474_e552);
+    assert_eq!(relro.memsz, 0x19e0);
+    assert!(elf_parser.program_header(11).is_none());
+    assert_eq!(elf_parser.relro(0x1000), Some(0x39000..0x3b000));
+    assert_eq!(
+        elf_parser.eh_frame_hdr(),
+        Some(0x32fc4 + interp_base..0x33908 + interp_base)
+    );
+    assert_eq!(elf_parser.image_range(0x1000), Some(0x1000..0x3d000));
+    assert_eq!(elf_parser.validate_segments(), Ok(()));
+    assert_eq!(elf_parser.validate_entry(), Ok(()));
+    assert_eq!(elf_parser.validate_congruence(), Ok(()));
+    assert_eq!(elf_parser.validate_offsets(elf_bytes.len()), Ok(()));
+    assert!(!elf_parser.has_wx_segment());
+    assert_eq!(
+        elf_parser.build_id(&elf_bytes),
+        Some(
+            &b"\xe4\xde\x03\x6b\x19\xe4\x76\x8e\x75\x91\xb5\x96\xc4\xbe\x9f\x90\x15\xf2\xd2\x8a"[..]
+        )
+    );
+    // NT_GNU_PROPERTY_TYPE_0 in an 8-aligned segment, then the build-id.
+    let notes = elf_parser
+        .notes(&elf_bytes)
+        .map(|note| (note.name, note.kind, note.desc.len()))
+        .collect::<Vec<_>>();
+    assert_eq!(notes, [("GNU", 5, 0x10), ("GNU", 3, 0x14)]);
+    assert_eq!(
+        elf_parser.gnu_properties(&elf_bytes),
+        GnuProperties {
+            ibt: true,
+            shstk: true,
+            bti: false,
+        }
+    );
+
+    assert_eq!(
+        elf_parser.symbol(&elf_bytes, "_dl_allocate_tls"),
+        Some(0x147a0 + interp_base)
+    );
+    // The unnamed undefined symbol at index 0 is never found.
+    assert_eq!(elf_parser.symbol(&elf_bytes, ""), None);
+
+    let dynamic = elf_parser.dynamic(&elf_bytes).unwrap().unwrap();
+    assert_eq!(dynamic.needed, 0);
+    assert_eq!(dynamic.strtab, Some(0x950 + interp_base));
+    assert_eq!(dynamic.strsz, 705);
+    assert_eq!(dynamic.symtab, Some(0x590 + interp_base));
+    assert_eq!(dynamic.syment, 24);
+    assert_eq!(dynamic.hash, Some(0x2f0 + interp_base));
+    assert_eq!(dynamic.gnu_hash, Some(0x430 + interp_base));
+    assert_eq!(dynamic.rela, Some(0xd58 + interp_base));
+    assert_eq!(dynamic.relasz, 3480);
+    assert_eq!(dynamic.relaent, 24);
+    assert_eq!(dynamic.jmprel, Some(0x1af0 + interp_base));
+    assert_eq!(dynamic.pltrelsz, 96);
+    assert_eq!(dynamic.pltgot, Some(0x3a000 + interp_base));
+    assert_eq!(dynamic.init_array, None);
+
+    let relocs = elf_parser
+        .relocations(&elf_bytes)
+        .unwrap()
+        .collect::<Vec<_>>();
+    // 145 in DT_RELA, and 4 R_X86_64_JUMP_SLOT in DT_JMPREL.
+    assert_eq!(relocs.len(), 145 + 4);
+    assert_eq!(relocs[0].offset, 0x38620 + interp_base);
+    assert_eq!(relocs[0].kind, 8);
+    assert_eq!(relocs[0].category, RelocKind::Relative);
+    assert_eq!(relocs[0].addend, 0x2f041);
+    assert!(relocs[145..].iter().all(|r| r.kind == 7));
+    assert!(relocs[145..].iter().all(|r| r.category == RelocKind::Other));
+    let mut relative = Vec::new();
+    elf_parser
+        .apply_relative(&elf_bytes, |addr, val| relative.push((addr, val)))
+        .unwrap();
+    assert_eq!(relative.len(), 142);
+    assert_eq!(relative[0], (0x38620 + interp_base, 0x2f041 + interp_base));
+}
+
+#[test]
+fn test_entry_with_base() {
+    let elf_bytes = common::load(include_bytes!("ld-linux-x86-64.so.2"));
+    let headers = common::headers(&elf_bytes);
+    let ldso_base = 0x7fff_0000_0000;
+    let biased = kernel_elf_parser::ELFParser::new(&headers, ldso_base).unwrap();
+    assert_eq!(biased.entry(), 0x20290 + ldso_base);
+
+    let unbiased = kernel_elf_parser::ELFParser::new(&headers, 0).unwrap();
+    assert_eq!(unbiased.entry(), 0x20290);
+    assert_eq!(unbiased.entry_with_base(ldso_base), Some(biased.entry()));
+    assert_eq!(unbiased.entry_with_base(usize::MAX), None);
+}
+
+#[test]
+fn test_needed_libs() {
+    // A PIE compiled by gcc, linked against libm and libc.
+    let elf_bytes = common::load(include_bytes!("elf_dynamic"));
+    let headers = common::headers(&elf_bytes);
+    let elf_parser = kernel_elf_parser::ELFParser::new(&headers, 0x4000_0000).unwrap();
+    assert!(elf_parser.is_pie());
+    assert!(elf_parser.is_dynamic());
+    assert!(!elf_parser.is_static());
+    assert!(elf_parser.needs_interp());
+    assert!(!elf_parser.is_static_pie());
+    assert_eq!(elf_parser.os_abi(), 0);
+    assert_eq!(elf_parser.validate_os_abi(), Ok(()));
+    let needed = elf_parser
+        .needed_libs(&elf_bytes)
+        .unwrap()
+        .collect::<Vec<_>>();
+    assert_eq!(needed, ["libm.so.6", "libc.so.6"]);
+    // Only GNU_PROPERTY_X86_ISA_1_NEEDED.
+    assert_eq!(
+        elf_parser.gnu_properties(&elf_bytes),
+        GnuProperties::default()
+    );
+    let dynamic = elf_parser.dynamic(&elf_bytes).unwrap().unwrap();
+    assert_eq!(dynamic.needed, 2);
+    assert_eq!(dynamic.preinit_array(), None);
+    assert_eq!(dynamic.init_array(), Some(0x4000_3de0..0x4000_3de8));
+    assert_eq!(dynamic.fini_array(), Some(0x4000_3de8..0x4000_3df0));
+    // A DT_INIT_ARRAYSZ running past the end of the address space.
+    let overflowing = DynamicInfo {
+        init_arraysz: u64::MAX,
+        ..dynamic
+    };
+    assert_eq!(overflowing.init_array(), None);
+
+    // Point DT_STRTAB out of the file.
+    let mut corrupt = elf_bytes.clone();
+    let strtab = (0x2df0..0x2df0 + 0x1d0)
+        .step_by(16)
+        .find(|&at| corrupt[at] == 5)
+        .unwrap();
+    corrupt[strtab + 8..strtab + 16].copy_from_slice(&0xffff_0000u64.to_le_bytes());
+    assert!(elf_parser.needed_libs(&corrupt).is_err());
+
+    // The interpreter needs nothing.
+    let elf_bytes = common::load(include_bytes!("ld-linux-x86-64.so.2"));
+    let headers = common::headers(&elf_bytes);
+    let elf_parser = kernel_elf_parser::ELFParser::new(&headers, 0).unwrap();
+    assert_eq!(elf_parser.needed_libs(&elf_bytes).unwrap().count(), 0);
+}
+
+#[test]
+fn test_soname() {
+    // Linked with `-Wl,-soname,ld-linux-x86-64.so.2`.
+    let elf_bytes = common::load(include_bytes!("ld-linux-x86-64.so.2"));
+    let headers = common::headers(&elf_bytes);
+    let elf_parser = kernel_elf_parser::ELFParser::new(&headers, 0).unwrap();
+    assert_eq!(
+        elf_parser.soname(&elf_bytes),
+        Ok(Some("ld-linux-x86-64.so.2"))
+    );
+
+    let elf_bytes = common::load(include_bytes!("elf_dynamic"));
+    let headers = common::headers(&elf_bytes);
+    let elf_parser = kernel_elf_parser::ELFParser::new(&headers, 0).unwrap();
+    assert_eq!(elf_parser.soname(&elf_bytes), Ok(None));
+}
+
+#[test]
+fn test_runpath() {
+    // Linked with `-Wl,-rpath,/opt/lib`, which emits DT_RUNPATH.
+    let elf_bytes = common::load(include_bytes!("elf_dynamic"));
+    let headers = common::headers(&elf_bytes);
+    let elf_parser = kernel_elf_parser::ELFParser::new(&headers, 0).unwrap();
+    assert_eq!(elf_parser.runpath(&elf_bytes), Ok(Some("/opt/lib")));
+    assert_eq!(elf_parser.rpath(&elf_bytes), Ok(None));
+
+    // Turn it into DT_RPATH, as with `-Wl,--disable-new-dtags`.
+    let mut rpath = elf_bytes.clone();
+    let runpath = (0x2df0..0x2df0 + 0x1d0)
+        .step_by(16)
+        .find(|&at| rpath[at] == 29)
+        .unwrap();
+    rpath[runpath] = 15;
+    assert_eq!(elf_parser.runpath(&rpath), Ok(None));
+    assert_eq!(elf_parser.rpath(&rpath), Ok(Some("/opt/lib")));
+}
+
+#[test]
+fn test_relr() {
+    // A PIE linked with `-z pack-relative-relocs`, whose relative relocations
+    // are all in DT_RELR.
+    let elf_bytes = common::load(include_bytes!("elf_relr"));
+    let headers = common::headers(&elf_bytes);
+    let base = 0x4000_0000;
+    let elf_parser = kernel_elf_parser::ELFParser::new(&headers, base).unwrap();
+    let dynamic = elf_parser.dynamic(&elf_bytes).unwrap().unwrap();
+    assert_eq!(dynamic.relr, Some(0x590 + base));
+    assert_eq!(dynamic.relrsz, 24);
+    assert_eq!(dynamic.relrent, 8);
+
+    let relocs = elf_parser
+        .relocations(&elf_bytes)
+        .unwrap()
+        .collect::<Vec<_>>();
+    assert_eq!(relocs.len(), 5 + 16);
+    // R_X86_64_GLOB_DAT from DT_RELA.
+    assert!(relocs[..5].iter().all(|r| r.kind == 6));
+    let mut expected = vec![0x3dd0, 0x3dd8, 0x4008, 0x4020];
+    expected.extend((0x4040..=0x4098).step_by(8));
+    let relative = relocs[5..]
+        .iter()
+        .inspect(|r| assert_eq!(r.kind, 8))
+        .map(|r| r.offset - base)
+        .collect::<Vec<_>>();
+    assert_eq!(relative, expected);
+
+    // The implicit addends are the link-time values in the file, e.g. the
+    // address of `a` in both `ptrs[0]` and `ptrs[4]`.
+    let mut written = Vec::new();
+    elf_parser
+        .apply_relative(&elf_bytes, |addr, value| written.push((addr, value)))
+        .unwrap();
+    assert_eq!(written.len(), 16);
+    assert!(written.iter().all(|&(_, value)| value > base));
+    assert_eq!(written[4].1, written[8].1);
+    // The addend is the value at the address in the file.
+    let slot = elf_parser
+        .read_at_vaddr(&elf_bytes, written[4].0, 8)
+        .unwrap();
+    assert_eq!(
+        usize::from_ne_bytes(slot.try_into().unwrap()) + base,
+        written[4].1
+    );
+}
+
+#[test]
+fn test_irelative() {
+    // A `-nostdlib -static-pie` executable calling an IFUNC, whose
+    // R_X86_64_IRELATIVE relocation is in DT_JMPREL.
+    let elf_bytes = common::load(include_bytes!("elf_ifunc"));
+    let headers = common::headers(&elf_bytes);
+    let base = 0x4000_0000;
+    let elf_parser = kernel_elf_parser::ELFParser::new(&headers, base).unwrap();
+    assert!(elf_parser.is_static_pie());
+    let relocs = elf_parser
+        .relocations(&elf_bytes)
+        .unwrap()
+        .collect::<Vec<_>>();
+    assert_eq!(relocs.len(), 2);
+    assert_eq!(relocs[0].category, RelocKind::Relative);
+    assert_eq!(relocs[1].kind, 37);
+    assert_eq!(relocs[1].category, RelocKind::IRelative);
+
+    let mut resolvers = Vec::new();
+    let mut written = Vec::new();
+    elf_parser
+        .apply_irelative(
+            &elf_bytes,
+            |resolver| {
+                resolvers.push(resolver);
+                0xdead_0000
+            },
+            |addr, value| written.push((addr, value)),
+        )
+        .unwrap();
+    assert_eq!(resolvers, [0x1026 + base]);
+    assert_eq!(written, [(0x4000 + base, 0xdead_0000)]);
+
+    // IRELATIVE relocations are left alone by `apply_relative`.
+    let mut relative = Vec::new();
+    elf_parser
+        .apply_relative(&elf_bytes, |addr, value| relative.push((addr, value)))
+        .unwrap();
+    assert_eq!(relative, [(0x4008 + base, 0x4010 + base)]);
+}
+
+#[test]
+fn test_symbol_relocations() {
+    let elf_bytes = common::load(include_bytes!("elf_dynamic"));
+    let headers = common::headers(&elf_bytes);
+    let base = 0x4000_0000;
+    let elf_parser = kernel_elf_parser::ELFParser::new(&headers, base).unwrap();
+    let relocs = elf_parser
+        .relocations(&elf_bytes)
+        .unwrap()
+        .collect::<Vec<_>>();
+    assert_eq!(relocs.len(), 8);
+    // R_X86_64_RELATIVE refers to no symbol.
+    assert!(relocs[..3].iter().all(|r| r.sym_index.is_none()));
+    // R_X86_64_GLOB_DAT refers to symbols 1 to 5.
+    let symbols = relocs[3..]
+        .iter()
+        .inspect(|r| assert_eq!((r.kind, r.category), (6, RelocKind::Other)))
+        .map(|r| elf_parser.dyn_symbol(&elf_bytes, r.sym_index.unwrap()))
+        .collect::<Vec<_>>();
+    assert_eq!(
+        symbols,
+        [
+            Some("__libc_start_main"),
+            Some("_ITM_deregisterTMCloneTable"),
+            Some("__gmon_start__"),
+            Some("_ITM_registerTMCloneTable"),
+            Some("__cxa_finalize"),
+        ]
+    );
+    assert_eq!(relocs[3].offset, 0x3fc0 + base);
+    // The null symbol has an empty name; entries past the file are rejected.
+    assert_eq!(elf_parser.dyn_symbol(&elf_bytes, 0), Some(""));
+    assert_eq!(elf_parser.dyn_symbol(&elf_bytes, usize::MAX), None);
+
+    let static_bytes = common::load(include_bytes!("elf_static"));
+    let static_headers = common::headers(&static_bytes);
+    let static_parser = kernel_elf_parser::ELFParser::new(&static_headers, 0).unwrap();
+    assert_eq!(static_parser.dyn_symbol(&static_bytes, 1), None);
+}
+
+#[cfg(feature = "alloc")]
+#[test]
+fn test_segments_vec() {
+    let elf_bytes = common::load(include_bytes!("ld-linux-x86-64.so.2"));
+    let headers = common::headers(&elf_bytes);
+    let elf_parser = kernel_elf_parser::ELFParser::new(&headers, 0x1000).unwrap();
+    let load = elf_parser.load_segments();
+    assert_eq!(load.len(), elf_parser.ph_load().count());
+    assert!(load.iter().all(|ph| ph.kind == common::PT_LOAD));
+    let all = elf_parser.all_segments();
+    assert_eq!(all.len(), elf_parser.phnum());
+    assert_eq!(all[4].kind, elf_parser.program_header(4).unwrap().kind);
+    assert_eq!(all[4].vaddr, elf_parser.program_header(4).unwrap().vaddr);
+}
+
+#[test]
+fn test_interp() {
+    let elf_bytes = common::load(include_bytes!("elf_dynamic"));
+    let headers = common::headers(&elf_bytes);
+    let elf_parser = ELFParser::new(&headers, 0).unwrap();
+    assert_eq!(
+        elf_parser.interp(&elf_bytes),
+        Ok(Some("/lib64/ld-linux-x86-64.so.2"))
+    );
+    assert_eq!(
+        elf_parser.interp(&elf_bytes[..0x320]),
+        Err(ElfParseError::Truncated)
+    );
+    // The path ends at 0x318 + 0x1c.
+    let mut corrupt = elf_bytes.clone();
+    corrupt[0x318 + 0x1b] = b'/';
+    assert_eq!(elf_parser.interp(&corrupt), Err(ElfParseError::BadInterp));
+
+    let elf_bytes = common::load(include_bytes!("ld-linux-x86-64.so.2"));
+    let headers = common::headers(&elf_bytes);
+    let elf_parser = ELFParser::new(&headers, 0).unwrap();
+    assert_eq!(elf_parser.interp(&elf_bytes), Ok(None));
+}
+
+#[cfg(feature = "alloc")]
+#[test]
+fn test_plan() {
+    let elf_bytes = common::load(include_bytes!("elf_dynamic"));
+    let headers = common::headers(&elf_bytes);
+    let base = 0x4000_0000;
+    let elf_parser = ELFParser::new(&headers, base).unwrap();
+    let plan = elf_parser.plan(&elf_bytes, 0x1000).unwrap();
+    assert_eq!(plan.entry, base + 0x1050);
+    assert_eq!(plan.image, base..base + 0x5000);
+    assert_eq!(
+        plan.regions,
+        elf_parser.merged_load_regions(0x1000).collect::<Vec<_>>()
+    );
+    assert_eq!(plan.regions.len(), 4);
+    assert_eq!(plan.regions[0].map_vaddr, base);
+    assert_eq!(plan.interp, Some("/lib64/ld-linux-x86-64.so.2"));
+    assert_eq!(plan.relro, Some(base + 0x3000..base + 0x4000));
+    assert!(!plan.executable_stack);
+}
+
+#[test]
+fn test_has_relocations() {
+    for (elf_bytes, expected) in [
+        (&include_bytes!("elf_dynamic")[..], true),
+        (include_bytes!("ld-linux-x86-64.so.2"), true),
+        (include_bytes!("elf_ifunc"), true),
+        (include_bytes!("elf_static"), false),
+        (include_bytes!("elf32_static"), false),
+    ] {
+        let elf_bytes = common::load(elf_bytes);
+        let headers = common::headers(&elf_bytes);
+        let elf_parser = ELFParser::new(&headers, 0x4000_0000).unwrap();
+        let has_relocations = elf_parser.has_relocations(&elf_bytes);
+        assert_eq!(has_relocations, expected);
+        assert_eq!(
+            has_relocations,
+            elf_parser.relocations(&elf_bytes).unwrap().next().is_some()
+        );
+    }
+}
+
+#[test]
+fn test_min_kernel_version() {
+    // glibc links `.note.ABI-tag` into executables.
+    let elf_bytes = common::load(include_bytes!("elf_dynamic"));
+    let headers = common::headers(&elf_bytes);
+    let elf_parser = kernel_elf_parser::ELFParser::new(&headers, 0).unwrap();
+    assert_eq!(elf_parser.min_kernel_version(&elf_bytes), Some((3, 2, 0)));
+
+    // But not into the dynamic linker.
+    let elf_bytes = common::load(include_bytes!("ld-linux-x86-64.so.2"));
+    let headers = common::headers(&elf_bytes);
+    let elf_parser = kernel_elf_parser::ELFParser::new(&headers, 0).unwrap();
+    assert_eq!(elf_parser.min_kernel_version(&elf_bytes), None);
+}
+
+#[test]
+fn test_aux_vector_with_interp() {
+    let elf_bytes = common::load(include_bytes!("elf_dynamic"));
+    let headers = common::headers(&elf_bytes);
+    let elf_parser = kernel_elf_parser::ELFParser::new(&headers, 0x4000_0000).unwrap();
+    let ldso_bytes = common::load(include_bytes!("ld-linux-x86-64.so.2"));
+    let ldso_headers = common::headers(&ldso_bytes);
+    let ldso_base = 0x7fff_0000_0000;
+    let ldso_parser = kernel_elf_parser::ELFParser::new(&ldso_headers, ldso_base).unwrap();
+
+    let auxv = elf_parser
+        .aux_vector(
+            0x1000,
+            100,
+            Some(ldso_base),
+            None,
+            Credentials::default(),
+            &[],
+        )
+        .unwrap()
+        .collect::<Vec<_>>();
+    let find = |at| {
+        auxv.iter()
+            .find(|entry| entry.get_type() == at)
+            .map(|entry| entry.value())
+    };
+    // The main program's headers, not those of the dynamic linker.
+    let phdr = elf_parser.phdr().unwrap();
+    assert_eq!(phdr, 0x4000_0040);
+    assert_ne!(phdr, ldso_parser.phdr().unwrap());
+    assert_eq!(find(AuxType::PHDR), Some(phdr));
+    assert_eq!(find(AuxType::PHNUM), Some(elf_parser.phnum()));
+    assert_eq!(find(AuxType::ENTRY), Some(elf_parser.entry()));
+    assert_eq!(find(AuxType::BASE), Some(ldso_base));
+}
+
+/// A file on a block device, read sector by sector.
+struct Disk {
+    data: Vec<u8>,
+    sectors_read: Cell<usize>,
+}
+
+impl ReadAt for Disk {
+    fn read_at(&self, offset: usize, buf: &mut [u8]) -> Result<(), ElfParseError> {
+        let end = offset + buf.len();
+        self.sectors_read
+            .set(self.sectors_read.get() + end.div_ceil(512) - offset / 512);
+        self.data.as_slice().read_at(offset, buf)
+    }
+}
+
+#[test]
+fn test_read_at() {
+    let disk = Disk {
+        data: common::load(include_bytes!("elf_dynamic")),
+        sectors_read: Cell::new(0),
+    };
+    let mut header = [0; 64];
+    disk.read_at(0, &mut header).unwrap();
+    let builder = ELFHeadersBuilder::new(&header).unwrap();
+    let ph_range = builder.ph_range().unwrap();
+    let mut ph = vec![0; (ph_range.end - ph_range.start) as usize];
+    disk.read_at(ph_range.start as usize, &mut ph).unwrap();
+    let headers = builder.build(&ph).unwrap();
+    let elf_parser = ELFParser::new(&headers, 0x4000_0000).unwrap();
+    assert_eq!(disk.sectors_read.get(), 3);
+
+    let interp = elf_parser
+        .program_header(1)
+        .filter(|ph| ph.kind == 3)
+        .unwrap();
+    let mut path = vec![0; interp.filesz as usize];
+    elf_parser
+        .read_segment_at(&disk, &interp, 0, &mut path)
+        .unwrap();
+    assert_eq!(path, b"/lib64/ld-linux-x86-64.so.2\0");
+    assert_eq!(
+        elf_parser.read_segment_at(&disk, &interp, 1, &mut path),
+        Err(ElfParseError::Truncated)
+    );
+
+    // Read the segments page by page.
+    for segment in elf_parser.ph_load() {
+        let expected = elf_parser.segment_data(&disk.data, &segment).unwrap();
+        let mut page = [0; 0x1000];
+        for (pos, chunk) in expected.chunks(page.len()).enumerate() {
+            let buf = &mut page[..chunk.len()];
+            elf_parser
+                .read_segment_at(&disk, &segment, pos * 0x1000, buf)
+                .unwrap();
+            assert_eq!(buf, chunk);
+        }
+    }
+
+    let note_segments = (0..elf_parser.phnum())
+        .filter_map(|idx| elf_parser.program_header(idx))
+        .filter(|ph| ph.kind == 4)
+        .collect::<Vec<_>>();
+    let mut bufs = [[0; 0x20]; 2];
+    let mut notes = Vec::new();
+    for (segment, buf) in note_segments.iter().zip(&mut bufs) {
+        notes.extend(elf_parser.read_notes(&disk, segment, buf).unwrap());
+    }
+    assert_eq!(notes, elf_parser.notes(&disk.data).collect::<Vec<_>>());
+    assert_eq!(notes.len(), 2);
+    // The buffer only holds the header of the note.
+    let mut short = [0; 0x18];
+    assert_eq!(
+        elf_parser
+            .read_notes(&disk, &note_segments[0], &mut short)
+            .unwrap()
+            .count(),
+        0
+    );
 }
@@ -0,0 +1,58 @@
+mod common;
+
+use kernel_elf_parser::{AuxType, Credentials, ELFParser, ElfParseError, GnuProperties, Machine};
+
+#[test]
+fn test_elf32_parser() {
+    // A static i386 elf file compiled by `gcc -m32 -nostdlib -static`.
+    let elf_bytes = common::load(include_bytes!("elf32_static"));
+    let headers = common::headers(&elf_bytes);
+    let elf_parser = ELFParser::new(&headers, 0x1000).unwrap();
+    assert_eq!(elf_parser.base(), 0);
+    assert_eq!(elf_parser.machine(), Machine::X86);
+    assert!(ELFParser::new_for(&headers, 0, Machine::X86).is_ok());
+    assert_eq!(
+        ELFParser::new_for(&headers, 0, Machine::X86_64).err(),
+        Some(ElfParseError::WrongArch)
+    );
+    assert_eq!(elf_parser.entry(), 0x804_9000);
+    assert_eq!(elf_parser.phent(), 32);
+    assert_eq!(elf_parser.phnum(), 5);
+    assert_eq!(elf_parser.version(), 1);
+    assert_eq!(elf_parser.phdr(), Ok(0x804_8034));
+
+    let segments = elf_parser.ph_load().collect::<Vec<_>>();
+    assert_eq!(segments.len(), 4);
+    assert_eq!(segments[1].vaddr, 0x804_9000);
+    assert_eq!(segments[1].offset, 0x1000);
+    assert_eq!(segments[3].memsz, 4);
+    assert!(segments[3].flags.is_write());
+
+    let auxv = elf_parser
+        .aux_vector(0x1000, 100, None, None, Credentials::default(), &[])
+        .unwrap()
+        .collect::<Vec<_>>();
+    let phent = auxv.iter().find(|e| e.get_type() == AuxType::PHENT);
+    assert_eq!(phent.map(|e| e.value()), Some(32));
+}
+
+#[test]
+fn test_elf32_gnu_properties() {
+    // Compiled by `gcc -m32 -nostdlib -static -fcf-protection=full`, whose
+    // property array is 4-aligned.
+    let elf_bytes = common::load(include_bytes!("elf32_cet"));
+    let headers = common::headers(&elf_bytes);
+    let elf_parser = ELFParser::new(&headers, 0).unwrap();
+    let properties = elf_parser.gnu_properties(&elf_bytes);
+    assert!(properties.ibt);
+    assert!(properties.shstk);
+    assert!(!properties.bti);
+
+    let elf_bytes = common::load(include_bytes!("elf32_static"));
+    let headers = common::headers(&elf_bytes);
+    let elf_parser = ELFParser::new(&headers, 0).unwrap();
+    assert_eq!(
+        elf_parser.gnu_properties(&elf_bytes),
+        GnuProperties::default()
+    );
+}
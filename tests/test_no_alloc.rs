@@ -0,0 +1,63 @@
+//! Tests on the API available without the `alloc` feature, to be run with
+//! `cargo test --features alloc` as well.
+mod common;
+
+use common::{ET_DYN, PF_R, PF_W, PF_X, Ph};
+use kernel_elf_parser::{
+    AuxEntry, AuxType, Credentials, ELFHeaders, ELFParser, ElfParseError, MAX_PHNUM, build_stack,
+};
+
+#[test]
+fn test_core_api() {
+    let data = common::elf64(
+        ET_DYN,
+        0x100,
+        &[
+            Ph::load(PF_R | PF_X, 0, 0, 0x1000, 0x1000),
+            Ph::load(PF_R | PF_W, 0x1000, 0x1000, 0x100, 0x2000),
+        ],
+    );
+    let headers = common::headers(&data);
+    let base = 0x4000_0000;
+    let parser = ELFParser::new(&headers, base).unwrap();
+    assert_eq!(parser.entry(), base + 0x100);
+    assert_eq!(parser.ph_load().count(), 2);
+    assert_eq!(parser.load_segments_sorted().count(), 2);
+    assert_eq!(parser.merged_load_regions(0x1000).count(), 2);
+    assert_eq!(parser.validate_segments(), Ok(()));
+
+    let mut auxv = [AuxEntry::new(AuxType::NULL, 0); 32];
+    let mut auxc = 0;
+    for entry in parser
+        .aux_vector(0x1000, 100, None, None, Credentials::default(), &[])
+        .unwrap()
+    {
+        auxv[auxc] = entry;
+        auxc += 1;
+    }
+    let auxv = &auxv[..auxc];
+    let value = |at| auxv.iter().find(|e| e.get_type() == at).map(|e| e.value());
+    assert_eq!(value(AuxType::ENTRY), Some(base + 0x100));
+    assert_eq!(value(AuxType::PHDR), Some(base + 64));
+    assert_eq!(auxv.last().unwrap().get_type(), AuxType::NULL);
+
+    let mut stack = [0u8; 0x400];
+    let stack_top = 0x7fff_0000;
+    let sp = build_stack(&[b"prog"], &[], auxv, stack_top, &mut stack).unwrap();
+    assert_eq!(sp % 16, 0);
+}
+
+#[test]
+fn test_phnum_limit() {
+    let phs = [Ph::load(PF_R, 0, 0, 0x100, 0x100); MAX_PHNUM + 1];
+    let data = common::elf64(ET_DYN, 0, &phs[..MAX_PHNUM]);
+    assert!(ELFHeaders::from_bytes(&data).is_ok());
+
+    let data = common::elf64(ET_DYN, 0, &phs);
+    let err = ELFHeaders::from_bytes(&data).err();
+    if cfg!(feature = "alloc") {
+        assert_eq!(err, None);
+    } else {
+        assert_eq!(err, Some(ElfParseError::TooManyHeaders));
+    }
+}
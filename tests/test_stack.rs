@@ -0,0 +1,121 @@
+use kernel_elf_parser::{AuxEntry, AuxType, StackError, align_stack_top, build_stack};
+
+fn read_usize(buf: &[u8], offset: usize) -> usize {
+    usize::from_ne_bytes(buf[offset..offset + 8].try_into().unwrap())
+}
+
+fn read_cstr(buf: &[u8], offset: usize) -> &[u8] {
+    let len = buf[offset..].iter().position(|&b| b == 0).unwrap();
+    &buf[offset..offset + len]
+}
+
+#[test]
+fn test_build_stack() {
+    let argv: [&[u8]; 2] = [b"/bin/app", b"-v"];
+    let envp: [&[u8]; 1] = [b"LOG=file"];
+    let auxv = [
+        AuxEntry::new(AuxType::PAGESZ, 0x1000),
+        AuxEntry::new(AuxType::ENTRY, 0x40_1000),
+    ];
+    let stack_top = 0x4000_0000;
+    let mut buf = [0xffu8; 0x200];
+    let sp = build_stack(&argv, &envp, &auxv, stack_top, &mut buf).unwrap();
+    assert_eq!(sp % 16, 0);
+
+    let bottom = stack_top - buf.len();
+    let at = |addr: usize| addr - bottom;
+    assert_eq!(read_usize(&buf, at(sp)), 2);
+    let argv0 = read_usize(&buf, at(sp + 8));
+    let argv1 = read_usize(&buf, at(sp + 16));
+    assert_eq!(read_cstr(&buf, at(argv0)), b"/bin/app");
+    assert_eq!(read_cstr(&buf, at(argv1)), b"-v");
+    assert_eq!(read_usize(&buf, at(sp + 24)), 0);
+    let envp0 = read_usize(&buf, at(sp + 32));
+    assert_eq!(read_cstr(&buf, at(envp0)), b"LOG=file");
+    assert_eq!(read_usize(&buf, at(sp + 40)), 0);
+    assert!(envp0 > argv1 && argv1 > argv0 && argv0 > sp + 48 + 4 * 16);
+
+    let auxv_at = at(sp + 48);
+    assert_eq!(read_usize(&buf, auxv_at), AuxType::PAGESZ as usize);
+    assert_eq!(read_usize(&buf, auxv_at + 8), 0x1000);
+    assert_eq!(read_usize(&buf, auxv_at + 16), AuxType::ENTRY as usize);
+    assert_eq!(read_usize(&buf, auxv_at + 24), 0x40_1000);
+    // AT_EXECFN is added pointing at argv[0].
+    assert_eq!(read_usize(&buf, auxv_at + 32), AuxType::EXECFN as usize);
+    assert_eq!(read_usize(&buf, auxv_at + 40), argv0);
+    assert_eq!(read_usize(&buf, auxv_at + 48), AuxType::NULL as usize);
+}
+
+#[test]
+fn test_build_stack_non_utf8() {
+    // A Latin-1 file name and an invalid UTF-8 sequence.
+    let argv: [&[u8]; 2] = [b"/tmp/caf\xe9", b"\xff\xfe"];
+    let envp: [&[u8]; 1] = [b"NAME=\xc3\x28"];
+    let stack_top = 0x4000_0000;
+    let mut buf = [0xffu8; 0x100];
+    let sp = build_stack(&argv, &envp, &[], stack_top, &mut buf).unwrap();
+    let at = |addr: usize| addr - (stack_top - buf.len());
+    for (i, arg) in argv.iter().chain(&envp).enumerate() {
+        // argv[i], or envp[0] after the NULL terminator of argv.
+        let ptr = read_usize(&buf, at(sp + 8 + (i + i / 2) * 8));
+        assert_eq!(
+            &buf[at(ptr)..at(ptr) + arg.len() + 1],
+            [arg, &b"\0"[..]].concat()
+        );
+    }
+}
+
+#[test]
+fn test_build_stack_execfn() {
+    let argv: [&[u8]; 1] = [b"app"];
+    let auxv = [
+        AuxEntry::new(AuxType::EXECFN, 0x3fff_0000),
+        AuxEntry::new(AuxType::NULL, 0),
+    ];
+    let stack_top = 0x4000_0000;
+    let mut buf = [0u8; 0x100];
+    let sp = build_stack(&argv, &[], &auxv, stack_top, &mut buf).unwrap();
+    let auxv_at = sp + 32 - (stack_top - buf.len());
+    // The given AT_EXECFN is kept and AT_NULL is not duplicated.
+    assert_eq!(read_usize(&buf, auxv_at), AuxType::EXECFN as usize);
+    assert_eq!(read_usize(&buf, auxv_at + 8), 0x3fff_0000);
+    assert_eq!(read_usize(&buf, auxv_at + 16), AuxType::NULL as usize);
+}
+
+#[test]
+fn test_build_stack_empty() {
+    let stack_top = 0x4000_0000;
+    let mut buf = [0xffu8; 64];
+    let sp = build_stack(&[], &[], &[], stack_top, &mut buf).unwrap();
+    assert_eq!(sp % 16, 0);
+    let at = sp - (stack_top - buf.len());
+    // argc, argv NULL, envp NULL and the AT_NULL entry.
+    assert_eq!(read_usize(&buf, at), 0);
+    assert_eq!(read_usize(&buf, at + 8), 0);
+    assert_eq!(read_usize(&buf, at + 16), 0);
+    assert_eq!(read_usize(&buf, at + 24), AuxType::NULL as usize);
+}
+
+#[test]
+fn test_build_stack_too_small() {
+    let argv: [&[u8]; 1] = [b"/bin/app"];
+    let mut buf = [0u8; 32];
+    assert_eq!(
+        build_stack(&argv, &[], &[], 0x4000_0000, &mut buf),
+        Err(StackError::BufferTooSmall)
+    );
+}
+
+#[test]
+fn test_align_stack_top() {
+    for sp in [0x4000_0000, 0x3fff_fff8, 0x3fff_fff3] {
+        for (argc, envc, auxc) in [(0, 0, 1), (1, 0, 2), (1, 1, 2), (2, 3, 17), (3, 3, 18)] {
+            let top = align_stack_top(sp, argc, envc, auxc).unwrap();
+            let size = (1 + (argc + 1) + (envc + 1) + auxc * 2) * 8;
+            assert!(top <= sp && sp - top < 16);
+            assert_eq!((top - size) % 16, 0);
+        }
+    }
+    // The frame doesn't fit below sp.
+    assert_eq!(align_stack_top(0x40, 2, 3, 17), None);
+}
@@ -1,29 +1,29 @@
-use kernel_elf_parser::ELFParser;
+mod common;
+
+use kernel_elf_parser::{
+    Credentials, ELFParser, ElfParseError, LoadSegment, MapRegion, MappingFlags, SectionInfo,
+};
 
 #[test]
 fn test_elf_parser() {
     // A simple elf file compiled by the x86_64-linux-musl-gcc.
-    let elf_bytes = include_bytes!("elf_static");
-    // Ensure the alignment of the byte array
-    let mut aligned_elf_bytes = unsafe {
-        let ptr = elf_bytes.as_ptr() as *mut u8;
-        std::slice::from_raw_parts_mut(ptr, elf_bytes.len())
-    }
-    .to_vec();
-    if aligned_elf_bytes.len() % 16 != 0 {
-        let padding = vec![0u8; 16 - aligned_elf_bytes.len() % 16];
-        aligned_elf_bytes.extend(padding);
-    }
-    let elf =
-        xmas_elf::ElfFile::new(aligned_elf_bytes.as_slice()).expect("Failed to read elf file");
+    let elf_bytes = common::load(include_bytes!("elf_static"));
+    let headers = common::headers(&elf_bytes);
 
     let interp_base = 0x1000;
-    let elf_parser = kernel_elf_parser::ELFParser::new(&elf, interp_base).unwrap();
+    let elf_parser = kernel_elf_parser::ELFParser::new(&headers, interp_base).unwrap();
     let base_addr = elf_parser.base();
     assert_eq!(base_addr, 0);
+    assert!(!elf_parser.is_pie());
+    assert!(!elf_parser.is_dynamic());
+    assert!(elf_parser.is_static());
+    assert!(!elf_parser.needs_interp());
+    assert!(!elf_parser.is_static_pie());
 
     let segments = elf_parser.ph_load().collect::<Vec<_>>();
     assert_eq!(segments.len(), 4);
+    assert_eq!(elf_parser.load_count(), elf_parser.ph_load().count());
+    assert!(elf_parser.load_count() < elf_parser.phnum());
     let mut last_start = 0;
     for segment in segments.iter() {
         // start vaddr should be sorted
@@ -31,12 +31,90 @@ fn test_elf_parser() {
         last_start = segment.vaddr;
     }
     assert_eq!(segments[0].vaddr, 0x400000);
+    let writable = elf_parser.writable_segments().collect::<Vec<_>>();
+    assert_eq!(writable.len(), 1);
+    assert_eq!(writable[0].vaddr, segments[3].vaddr);
+    let readonly = elf_parser
+        .readonly_segments()
+        .map(|ph| ph.perm_str())
+        .collect::<Vec<_>>();
+    assert_eq!(readonly, ["r--", "r-x", "r--"]);
+    assert!(segments[..3].iter().all(|ph| ph.bss_range().is_none()));
+    assert_eq!(segments[3].bss_range(), Some(0x405130..0x4057d8));
+    let text = elf_parser.segment_data(&elf_bytes, &segments[1]).unwrap();
+    assert_eq!(text.len() as u64, segments[1].filesz);
+    assert_eq!(text, &elf_bytes[segments[1].offset..][..text.len()]);
+    assert_eq!(
+        elf_parser.segment_data(&elf_bytes[..0x3000], &segments[3]),
+        Err(ElfParseError::Truncated)
+    );
+
+    let regions = elf_parser.ph_load_aligned(0x1000).collect::<Vec<_>>();
+    assert_eq!(regions.len(), 4);
+    assert_eq!(
+        regions[3],
+        MapRegion {
+            map_vaddr: 0x404000,
+            map_offset: 0x3000,
+            map_size: 0x2000,
+            flags: MappingFlags::READ | MappingFlags::WRITE | MappingFlags::USER,
+            first_page_pad: 0xfd8,
+        }
+    );
+    assert!(elf_parser.tls_template().is_none());
+    assert!(!elf_parser.executable_stack());
+    assert_eq!(elf_parser.eh_frame_hdr(), None);
+    let first = elf_parser.first_load().unwrap();
+    let last = elf_parser.last_load().unwrap();
+    assert_eq!(first.vaddr, 0x40_0000);
+    assert_eq!(last.vaddr, 0x40_4fd8);
+    assert_eq!(last.vaddr + last.memsz as usize, 0x40_57d8);
+    assert_eq!(elf_parser.initial_brk(0x1000), Some(0x40_6000));
+    // Through the trait, as generic mapping code sees them.
+    let perms = |seg: &dyn LoadSegment| (seg.is_read(), seg.is_write(), seg.is_exec());
+    assert_eq!(perms(&last), (true, true, false));
+    assert_eq!(LoadSegment::filesz(&last), 0x158);
+    assert!(!elf_parser.has_wx_segment());
+    assert_eq!(elf_parser.validate_segments(), Ok(()));
+    assert_eq!(elf_parser.validate_entry(), Ok(()));
+    assert_eq!(elf_parser.validate_congruence(), Ok(()));
+    assert_eq!(elf_parser.validate_offsets(elf_bytes.len()), Ok(()));
+    assert_eq!(elf_parser.image_range(0x1000), Some(0x400000..0x406000));
+    assert_eq!(elf_parser.page_count(0x1000), Some(6));
+    assert_eq!(elf_parser.memory_size(), 0x57d8);
+    assert_eq!(elf_parser.relro(0x1000), Some(0x404000..0x405000));
+    assert_eq!(elf_parser.dynamic(&elf_bytes), Ok(None));
+    assert_eq!(elf_parser.needed_libs(&elf_bytes).unwrap().count(), 0);
+    assert_eq!(elf_parser.soname(&elf_bytes), Ok(None));
+    assert_eq!(elf_parser.relocations(&elf_bytes).unwrap().count(), 0);
+    // Only `.symtab` is present.
+    assert_eq!(elf_parser.symbol(&elf_bytes, "_start"), Some(0x40102f));
+    assert_eq!(elf_parser.symbol(&elf_bytes, "main"), Some(0x401139));
+    assert_eq!(elf_parser.symbol(&elf_bytes, "missing"), None);
+
+    let sections = elf_parser.sections(&elf_bytes).collect::<Vec<_>>();
+    assert_eq!(sections.len(), 26);
+    assert_eq!(sections[0].name, "");
+    assert_eq!(
+        sections[12],
+        SectionInfo {
+            name: ".bss",
+            addr: 0x405140,
+            offset: 0x4130,
+            size: 0x698,
+            sh_type: 8,
+        }
+    );
+    assert_eq!(sections[25].name, ".shstrtab");
 
     test_ustack(&elf_parser);
 }
 
 fn test_ustack(elf_parser: &ELFParser) {
-    let auxv = elf_parser.aux_vector(0x1000, None).collect::<Vec<_>>();
+    let auxv = elf_parser
+        .aux_vector(0x1000, 100, None, None, Credentials::default(), &[])
+        .unwrap()
+        .collect::<Vec<_>>();
     // let phent = auxv.get(&AT_PHENT).unwrap();
     // assert_eq!(*phent, 56);
     auxv.iter().for_each(|entry| {
@@ -45,13 +123,16 @@ fn test_ustack(elf_parser: &ELFParser) {
         }
     });
 
-    let args: Vec<String> = vec!["arg1".to_string(), "arg2".to_string(), "arg3".to_string()];
-    let envs: Vec<String> = vec!["LOG=file".to_string()];
+    #[cfg(feature = "alloc")]
+    {
+        let args: Vec<String> = vec!["arg1".to_string(), "arg2".to_string(), "arg3".to_string()];
+        let envs: Vec<String> = vec!["LOG=file".to_string()];
 
-    // The highest address of the user stack.
-    let ustack_end = 0x4000_0000;
+        // The highest address of the user stack.
+        let ustack_end = 0x4000_0000;
 
-    let stack_data = kernel_elf_parser::app_stack_region(&args, &envs, &auxv, ustack_end);
-    // The first 8 bytes of the stack is the number of arguments.
-    assert_eq!(stack_data[0..8], [3, 0, 0, 0, 0, 0, 0, 0]);
+        let stack_data = kernel_elf_parser::app_stack_region(&args, &envs, &auxv, ustack_end);
+        // The first 8 bytes of the stack is the number of arguments.
+        assert_eq!(stack_data[0..8], [3, 0, 0, 0, 0, 0, 0, 0]);
+    }
 }
@@ -0,0 +1,1476 @@
+//! Tests on hand-crafted ELF files covering layouts the fixtures don't have.
+mod common;
+
+use common::{
+    ET_DYN, ET_EXEC, PF_R, PF_W, PF_X, PT_DYNAMIC, PT_GNU_PROPERTY, PT_GNU_STACK, PT_NOTE, PT_PHDR,
+    PT_TLS, Ph,
+};
+use kernel_elf_parser::{
+    AuxType, Credentials, ELFHeaders, ELFHeadersBuilder, ELFPH, ELFParser, ElfParseError, Machine,
+    MappingFlags, Note, ProgramHeaders, RelocKind, TlsInfo,
+};
+
+#[test]
+fn test_tls_template() {
+    let mut tls = Ph::new(PT_TLS, PF_R, 0x1f00, 0x2f00, 0x10, 0x48);
+    tls.align = 0x40;
+    let data = common::elf64(
+        ET_DYN,
+        0x1000,
+        &[Ph::load(PF_R | PF_W, 0x1000, 0x2000, 0x1000, 0x1000), tls],
+    );
+    let headers = common::headers(&data);
+    let parser = ELFParser::new(&headers, 0x10_0000).unwrap();
+    assert_eq!(
+        parser.tls_template(),
+        Some(TlsInfo {
+            vaddr: 0x10_2f00,
+            filesz: 0x10,
+            memsz: 0x48,
+            align: 0x40,
+        })
+    );
+}
+
+#[test]
+fn test_mapping_flags() {
+    let data = common::elf64(
+        ET_DYN,
+        0x1000,
+        &[
+            Ph::load(PF_R | PF_X, 0, 0, 0x1000, 0x1000),
+            Ph::load(PF_W, 0x1000, 0x1000, 0x1000, 0x1000),
+            Ph::load(PF_R | PF_W, 0x2000, 0x2000, 0x1000, 0x1000),
+        ],
+    );
+    let headers = common::headers(&data);
+    let parser = ELFParser::new(&headers, 0).unwrap();
+    let flags = parser
+        .ph_load()
+        .map(|ph| ph.mapping_flags())
+        .collect::<Vec<_>>();
+    assert_eq!(
+        flags,
+        [
+            MappingFlags::READ | MappingFlags::EXECUTE | MappingFlags::USER,
+            MappingFlags::WRITE | MappingFlags::USER,
+            MappingFlags::READ | MappingFlags::WRITE | MappingFlags::USER,
+        ]
+    );
+    assert!(!parser.has_wx_segment());
+}
+
+#[test]
+fn test_wx_segment() {
+    let data = common::elf64(
+        ET_DYN,
+        0,
+        &[
+            Ph::load(PF_R, 0, 0, 0x1000, 0x1000),
+            Ph::load(PF_R | PF_W | PF_X, 0x1000, 0x1000, 0x1000, 0x1000),
+        ],
+    );
+    let headers = common::headers(&data);
+    let parser = ELFParser::new(&headers, 0).unwrap();
+    assert!(parser.has_wx_segment());
+}
+
+#[test]
+fn test_dynamic_malformed() {
+    // The dynamic section right after the program headers, with no DT_NULL.
+    let offset = 64 + 56 * 2;
+    let mut data = common::elf64(
+        ET_DYN,
+        0x1000,
+        &[
+            Ph::load(PF_R | PF_W, 0, 0, 0x1000, 0x1000),
+            Ph::new(PT_DYNAMIC, PF_R | PF_W, offset, offset, 32, 32),
+        ],
+    );
+    for (tag, val) in [(5u64, 0x200u64), (10, 0x10)] {
+        data.extend_from_slice(&tag.to_le_bytes());
+        data.extend_from_slice(&val.to_le_bytes());
+    }
+    let headers = common::headers(&data);
+    let parser = ELFParser::new(&headers, 0).unwrap();
+    assert_eq!(parser.dynamic(&data), Err(ElfParseError::BadDynamic));
+    // Out of the bounds of the file.
+    assert_eq!(
+        parser.dynamic(&data[..offset as usize + 16]),
+        Err(ElfParseError::Truncated)
+    );
+
+    // A DT_RELA address overflowing once biased.
+    let mut data = common::elf64(
+        ET_DYN,
+        0x1000,
+        &[
+            Ph::load(PF_R | PF_W, 0, 0, 0x1000, 0x1000),
+            Ph::new(PT_DYNAMIC, PF_R | PF_W, offset, offset, 32, 32),
+        ],
+    );
+    for (tag, val) in [(7u64, u64::MAX - 0xff), (0, 0)] {
+        data.extend_from_slice(&tag.to_le_bytes());
+        data.extend_from_slice(&val.to_le_bytes());
+    }
+    let headers = common::headers(&data);
+    let parser = ELFParser::new(&headers, 0x1000).unwrap();
+    assert_eq!(parser.dynamic(&data), Err(ElfParseError::BadDynamic));
+    assert!(!parser.has_relocations(&data));
+}
+
+#[test]
+fn test_program_headers_decoded_once() {
+    let data = common::elf64(
+        ET_DYN,
+        0x1000,
+        &[
+            Ph::load(PF_R | PF_X, 0, 0, 0x1000, 0x1000),
+            Ph::load(PF_R | PF_W, 0x1000, 0x1000, 0x100, 0x2000),
+        ],
+    );
+    let builder = ELFHeadersBuilder::new(&data).unwrap();
+    let range = builder.ph_range().unwrap();
+    let mut table = data[range.start as usize..range.end as usize].to_vec();
+    let headers = builder.build(&table).unwrap();
+    // The accessors don't read the table again once the headers are built.
+    table.fill(0xff);
+    drop(table);
+    let parser = ELFParser::new(&headers, 0x4000_0000).unwrap();
+    let segments = parser
+        .ph_load()
+        .map(|ph| (ph.vaddr, ph.memsz))
+        .collect::<Vec<_>>();
+    assert_eq!(segments, [(0x4000_0000, 0x1000), (0x4000_1000, 0x2000)]);
+    assert_eq!(parser.phdr(), Ok(0x4000_0040));
+    assert_eq!(parser.load_count(), 2);
+}
+
+#[test]
+fn test_parse_errors() {
+    let data = common::elf64(ET_DYN, 0x1000, &[Ph::load(PF_R, 0, 0, 0x1000, 0x1000)]);
+    let err = |data: &[u8]| ELFHeadersBuilder::new(data).err();
+    assert_eq!(err(&data[..8]), Some(ElfParseError::Truncated));
+    assert_eq!(err(&data[..32]), Some(ElfParseError::Truncated));
+
+    let mut bad_magic = data.clone();
+    bad_magic[1] = b'X';
+    assert_eq!(err(&bad_magic), Some(ElfParseError::BadMagic));
+
+    let mut bad_class = data.clone();
+    bad_class[4] = 3;
+    assert_eq!(err(&bad_class), Some(ElfParseError::UnsupportedClass));
+
+    let mut big_endian = data.clone();
+    big_endian[5] = 2;
+    assert_eq!(err(&big_endian), Some(ElfParseError::WrongEndian));
+
+    // The program header table is shorter than declared.
+    let builder = ELFHeadersBuilder::new(&data).unwrap();
+    assert_eq!(
+        builder.build(&data[64..64 + 40]).err(),
+        Some(ElfParseError::Truncated)
+    );
+    assert_eq!(
+        ELFHeaders::from_bytes(&data[..64 + 40]).err(),
+        Some(ElfParseError::Truncated)
+    );
+
+    // The end of the program header table overflows.
+    let mut huge_phoff = data.clone();
+    huge_phoff[32..40].copy_from_slice(&0xffff_ffff_ffff_fff0u64.to_le_bytes());
+    let builder = ELFHeadersBuilder::new(&huge_phoff).unwrap();
+    assert_eq!(builder.ph_range(), Err(ElfParseError::Truncated));
+    assert_eq!(
+        ELFHeaders::from_bytes(&huge_phoff).err(),
+        Some(ElfParseError::Truncated)
+    );
+}
+
+#[test]
+fn test_image_range_empty() {
+    let data = common::elf64(ET_DYN, 0, &[Ph::new(PT_TLS, PF_R, 0, 0, 0, 0)]);
+    let headers = common::headers(&data);
+    let parser = ELFParser::new(&headers, 0x1000).unwrap();
+    assert_eq!(parser.image_range(0x1000), Some(0..0));
+    assert_eq!(parser.page_count(0x1000), Some(0));
+    assert_eq!(parser.memory_size(), 0);
+    // No section headers either.
+    assert_eq!(parser.sections(&data).count(), 0);
+}
+
+#[test]
+fn test_huge_align() {
+    let mut text = Ph::load(PF_R | PF_X, 0x20_0000, 0x20_0000, 0x1000, 0x1000);
+    text.align = 0x20_0000;
+    let data = common::elf64(
+        ET_DYN,
+        0x20_0000,
+        &[Ph::load(PF_R, 0, 0, 0x1000, 0x1000), text],
+    );
+    let headers = common::headers(&data);
+
+    let parser = ELFParser::new(&headers, 0x4000_0000).unwrap();
+    let segments = parser.ph_load().collect::<Vec<_>>();
+    assert_eq!(segments[0].align, 0x1000);
+    assert_eq!(segments[1].align, 0x20_0000);
+    assert_eq!(parser.max_align(0x1000), 0x20_0000);
+    assert!(segments.iter().all(|ph| ph.is_congruent()));
+
+    // A bias which is only page aligned breaks the 2 MiB alignment.
+    let parser = ELFParser::new(&headers, 0x4000_1000).unwrap();
+    let segments = parser.ph_load().collect::<Vec<_>>();
+    assert!(segments[0].is_congruent());
+    assert!(!segments[1].is_congruent());
+}
+
+#[test]
+fn test_mixed_page_sizes() {
+    let mut text = Ph::load(PF_R | PF_X, 0, 0, 0x30_0000, 0x30_0000);
+    text.align = 0x20_0000;
+    let data = common::elf64(
+        ET_DYN,
+        0x1000,
+        &[
+            text,
+            Ph::load(PF_R | PF_W, 0x30_0800, 0x50_0800, 0x100, 0x2000),
+        ],
+    );
+    let headers = common::headers(&data);
+    let parser = ELFParser::new(&headers, 0x4000_0000).unwrap();
+    let page_size_for = |ph: &ELFPH| {
+        if ph.align >= 0x20_0000 {
+            0x20_0000
+        } else {
+            0x1000
+        }
+    };
+    let regions = parser
+        .ph_load_aligned_with(page_size_for)
+        .collect::<Vec<_>>();
+    // Huge pages for the text segment.
+    assert_eq!(regions[0].map_vaddr, 0x4000_0000);
+    assert_eq!(regions[0].map_offset, 0);
+    assert_eq!(regions[0].map_size, 0x40_0000);
+    // Base pages for the data segment.
+    assert_eq!(regions[1].map_vaddr, 0x4050_0000);
+    assert_eq!(regions[1].map_offset, 0x30_0000);
+    assert_eq!(regions[1].map_size, 0x3000);
+    assert_eq!(regions[1].first_page_pad, 0x800);
+    // The file data stays in place in both.
+    for (region, ph) in regions.iter().zip(parser.ph_load()) {
+        assert_eq!(region.map_vaddr + (ph.offset - region.map_offset), ph.vaddr);
+    }
+    // The same as `ph_load_aligned` with a single size.
+    assert!(
+        parser
+            .ph_load_aligned_with(|_| 0x1000)
+            .eq(parser.ph_load_aligned(0x1000))
+    );
+}
+
+#[test]
+fn test_merged_mixed_page_sizes() {
+    let mut text = Ph::load(PF_R | PF_X, 0, 0, 0x30_0000, 0x30_0000);
+    text.align = 0x20_0000;
+    let data = common::elf64(
+        ET_DYN,
+        0x1000,
+        &[
+            text,
+            Ph::load(PF_R | PF_W, 0x30_0000, 0x50_0000, 0x1000, 0x1000),
+            Ph::load(PF_R | PF_W, 0x30_1000, 0x50_1000, 0x800, 0x2000),
+        ],
+    );
+    let headers = common::headers(&data);
+    let parser = ELFParser::new(&headers, 0x4000_0000).unwrap();
+    let page_size_for = |ph: &ELFPH| {
+        if ph.align >= 0x20_0000 {
+            0x20_0000
+        } else {
+            0x1000
+        }
+    };
+    let regions = parser
+        .merged_load_regions_with(page_size_for)
+        .map(|region| (region.map_vaddr, region.map_offset, region.map_size))
+        .collect::<Vec<_>>();
+    // The huge-page text stays whole, the base-page data segments merge.
+    assert_eq!(
+        regions,
+        [
+            (0x4000_0000, 0, 0x40_0000),
+            (0x4050_0000, 0x30_0000, 0x3000)
+        ]
+    );
+    // The same as `merged_load_regions` with a single size.
+    assert!(
+        parser
+            .merged_load_regions_with(|_| 0x1000)
+            .eq(parser.merged_load_regions(0x1000))
+    );
+
+    // A data segment in the last huge page of the text is not merged.
+    let data = common::elf64(
+        ET_DYN,
+        0x1000,
+        &[
+            text,
+            Ph::load(PF_R | PF_W, 0x30_0800, 0x30_0800, 0x100, 0x2000),
+        ],
+    );
+    let headers = common::headers(&data);
+    let parser = ELFParser::new(&headers, 0x4000_0000).unwrap();
+    assert!(
+        parser
+            .merged_load_regions_with(page_size_for)
+            .eq(parser.ph_load_aligned_with(page_size_for))
+    );
+}
+
+#[test]
+fn test_max_align() {
+    let mut phs = [
+        Ph::load(PF_R, 0, 0, 0x1000, 0x1000),
+        Ph::load(PF_R | PF_X, 0x1000, 0x1_0000, 0x1000, 0x1000),
+        Ph::load(PF_R | PF_W, 0x2000, 0x2_0000, 0x100, 0x1000),
+    ];
+    (phs[0].align, phs[1].align, phs[2].align) = (0, 0x1_0000, 1);
+    let data = common::elf64(ET_DYN, 0, &phs);
+    let headers = common::headers(&data);
+    let parser = ELFParser::new(&headers, 0).unwrap();
+    assert_eq!(parser.max_align(0x1000), 0x1_0000);
+
+    // No constraint beyond the page size.
+    (phs[0].align, phs[1].align) = (0, 1);
+    let data = common::elf64(ET_DYN, 0, &phs);
+    let headers = common::headers(&data);
+    let parser = ELFParser::new(&headers, 0).unwrap();
+    assert_eq!(parser.max_align(0x4000), 0x4000);
+}
+
+#[test]
+fn test_unsupported_type() {
+    // A relocatable object compiled by `gcc -c`.
+    let data = common::load(include_bytes!("object.o"));
+    let headers = common::headers(&data);
+    assert_eq!(
+        ELFParser::new(&headers, 0).err(),
+        Some(ElfParseError::UnsupportedType)
+    );
+    // A core dump.
+    let mut data = common::elf64(ET_DYN, 0, &[Ph::load(PF_R, 0, 0, 0x1000, 0x1000)]);
+    data[16] = 4;
+    let headers = common::headers(&data);
+    assert_eq!(
+        ELFParser::new(&headers, 0).err(),
+        Some(ElfParseError::UnsupportedType)
+    );
+}
+
+#[test]
+fn test_phdr_not_mapped() {
+    // The program header table at offset 64 is not covered by the LOAD
+    // segment, which starts at offset 0x1000.
+    let data = common::elf64(
+        ET_DYN,
+        0x1000,
+        &[Ph::load(PF_R | PF_X, 0x1000, 0x1000, 0x1000, 0x1000)],
+    );
+    let headers = common::headers(&data);
+    let parser = ELFParser::new(&headers, 0).unwrap();
+    assert_eq!(parser.phdr(), Err(ElfParseError::PhdrNotMapped));
+    assert_eq!(
+        parser
+            .aux_vector(0x1000, 100, None, None, Credentials::default(), &[])
+            .err(),
+        Some(ElfParseError::PhdrNotMapped)
+    );
+
+    // The kernel maps the table on its own.
+    let mapped = 0x7fff_f000;
+    assert_eq!(parser.phdr_fallback(mapped), mapped);
+    let auxv = parser
+        .aux_builder()
+        .phdr(mapped)
+        .build()
+        .unwrap()
+        .collect::<Vec<_>>();
+    let phdr = auxv.iter().find(|e| e.get_type() == AuxType::PHDR).unwrap();
+    assert_eq!(phdr.value(), mapped);
+}
+
+#[test]
+fn test_phdr_segment() {
+    // PT_PHDR and the LOAD segment covering the program header table must
+    // yield the same address.
+    let load = Ph::load(PF_R | PF_X, 0, 0x10000, 0x2000, 0x2000);
+    let phdr = Ph::new(PT_PHDR, PF_R, 64, 0x10040, 2 * 56, 2 * 56);
+    let with_phdr = common::elf64(ET_DYN, 0x10000, &[phdr, load]);
+    let without_phdr = common::elf64(ET_DYN, 0x10000, &[load]);
+
+    let headers = common::headers(&with_phdr);
+    let parser = ELFParser::new(&headers, 0x4000_0000).unwrap();
+    assert_eq!(parser.phdr(), Ok(0x4001_0040));
+
+    let headers = common::headers(&without_phdr);
+    let parser = ELFParser::new(&headers, 0x4000_0000).unwrap();
+    assert_eq!(parser.phdr(), Ok(0x4001_0040));
+    // The fallback is only used if the table is not mapped.
+    assert_eq!(parser.phdr_fallback(0x7fff_f000), 0x4001_0040);
+}
+
+#[test]
+fn test_phdr_nonzero_offset() {
+    // The program header table is moved to 0x1040, in the LOAD segment whose
+    // `p_offset` is its alignment.
+    let phs = [
+        Ph::load(PF_R | PF_X, 0x1000, 0x1000, 0x1000, 0x1000),
+        Ph::load(PF_R | PF_W, 0x2000, 0x3000, 0x100, 0x100),
+    ];
+    let mut data = common::elf64(ET_DYN, 0x1000, &phs);
+    let table = data[64..64 + 2 * 56].to_vec();
+    data.resize(0x2100, 0);
+    data[0x1040..0x1040 + table.len()].copy_from_slice(&table);
+    data[32..40].copy_from_slice(&0x1040u64.to_le_bytes());
+    data[64..64 + table.len()].fill(0);
+
+    let headers = common::headers(&data);
+    let parser = ELFParser::new(&headers, 0).unwrap();
+    assert_eq!(parser.phdr(), Ok(0x1040));
+    let parser = ELFParser::new(&headers, 0x4000_0000).unwrap();
+    assert_eq!(parser.phdr(), Ok(0x4000_1040));
+
+    // The same with a link-time address other than the offset.
+    let mut moved = data.clone();
+    moved[0x1040 + 16..0x1040 + 24].copy_from_slice(&0x40_1000u64.to_le_bytes());
+    moved[16..18].copy_from_slice(&common::ET_EXEC.to_le_bytes());
+    let headers = common::headers(&moved);
+    let parser = ELFParser::new(&headers, 0).unwrap();
+    assert_eq!(parser.phdr(), Ok(0x40_1040));
+
+    // The table must not run past the file-backed part of the segment.
+    let mut straddling = data.clone();
+    straddling[0x1fc0..0x1fc0 + table.len()].copy_from_slice(&table);
+    straddling[32..40].copy_from_slice(&0x1fc0u64.to_le_bytes());
+    let headers = common::headers(&straddling);
+    let parser = ELFParser::new(&headers, 0).unwrap();
+    assert_eq!(parser.phdr(), Err(ElfParseError::PhdrNotMapped));
+}
+
+#[test]
+fn test_executable_stack() {
+    let load = Ph::load(PF_R | PF_X, 0, 0, 0x1000, 0x1000);
+    for (stack, executable) in [
+        (None, false),
+        (Some(PF_R | PF_W), false),
+        (Some(PF_R | PF_W | PF_X), true),
+    ] {
+        let mut phs = vec![load];
+        phs.extend(stack.map(|flags| Ph::new(PT_GNU_STACK, flags, 0, 0, 0, 0)));
+        let data = common::elf64(ET_DYN, 0, &phs);
+        let headers = common::headers(&data);
+        let parser = ELFParser::new(&headers, 0).unwrap();
+        assert_eq!(parser.executable_stack(), executable);
+    }
+}
+
+#[test]
+fn test_build_id() {
+    // Two notes in one segment: a vendor note with a padded name and
+    // descriptor, then the build-id.
+    let offset = 64 + 56 * 2;
+    let mut notes = Vec::new();
+    for (name, kind, desc) in [
+        (&b"Linux\0"[..], 3u32, &[1u8, 2, 3, 4, 5][..]),
+        (b"GNU\0", 3, &[0xab; 20]),
+    ] {
+        notes.extend_from_slice(&(name.len() as u32).to_le_bytes());
+        notes.extend_from_slice(&(desc.len() as u32).to_le_bytes());
+        notes.extend_from_slice(&kind.to_le_bytes());
+        notes.extend_from_slice(name);
+        notes.resize(notes.len().next_multiple_of(4), 0);
+        notes.extend_from_slice(desc);
+        notes.resize(notes.len().next_multiple_of(4), 0);
+    }
+    let notes_len = notes.len() as u64;
+    let mut note = Ph::new(PT_NOTE, PF_R, offset, offset, notes_len, 0);
+    note.memsz = note.filesz;
+    note.align = 4;
+    let mut data = common::elf64(ET_DYN, 0, &[Ph::load(PF_R, 0, 0, 0x1000, 0x1000), note]);
+    data.extend_from_slice(&notes);
+    let headers = common::headers(&data);
+    let parser = ELFParser::new(&headers, 0).unwrap();
+    assert_eq!(parser.build_id(&data), Some(&[0xab; 20][..]));
+    let notes = parser.notes(&data).collect::<Vec<_>>();
+    assert_eq!(notes.len(), 2);
+    assert_eq!(
+        notes[0],
+        Note {
+            name: "Linux",
+            kind: 3,
+            desc: &[1, 2, 3, 4, 5],
+        }
+    );
+    assert_eq!(notes[1].name, "GNU");
+    // The segment is out of the bounds of the file.
+    assert_eq!(parser.build_id(&data[..data.len() - 4]), None);
+    assert_eq!(parser.notes(&data[..data.len() - 4]).count(), 0);
+
+    // The segment ends within the build-id note.
+    let mut truncated = data.clone();
+    truncated[64 + 56 + 32..64 + 56 + 40].copy_from_slice(&(notes_len - 4).to_le_bytes());
+    let headers = common::headers(&truncated);
+    let parser = ELFParser::new(&headers, 0).unwrap();
+    assert_eq!(parser.build_id(&truncated), None);
+    assert_eq!(parser.notes(&truncated).count(), 1);
+}
+
+#[test]
+fn test_gnu_properties() {
+    // GNU_PROPERTY_AARCH64_FEATURE_1_AND with BTI, padded to 8.
+    let offset = 64 + 56 * 2;
+    let mut note = Vec::new();
+    for word in [
+        4u32,
+        16,
+        5,
+        u32::from_le_bytes(*b"GNU\0"),
+        0xc000_0000,
+        4,
+        1,
+        0,
+    ] {
+        note.extend_from_slice(&word.to_le_bytes());
+    }
+    let mut property = Ph::new(PT_GNU_PROPERTY, PF_R, offset, offset, note.len() as u64, 0);
+    property.memsz = property.filesz;
+    property.align = 8;
+    let mut data = common::elf64(ET_DYN, 0, &[Ph::load(PF_R, 0, 0, 0x1000, 0x1000), property]);
+    data.extend_from_slice(&note);
+    // EM_AARCH64
+    data[18..20].copy_from_slice(&183u16.to_le_bytes());
+    let headers = common::headers(&data);
+    let parser = ELFParser::new(&headers, 0).unwrap();
+    let properties = parser.gnu_properties(&data);
+    assert!(properties.bti);
+    assert!(!properties.ibt && !properties.shstk);
+    // The segment is out of the bounds of the file.
+    assert!(!parser.gnu_properties(&data[..data.len() - 8]).bti);
+
+    // The same property type means nothing on x86_64.
+    data[18..20].copy_from_slice(&0x3eu16.to_le_bytes());
+    let headers = common::headers(&data);
+    let parser = ELFParser::new(&headers, 0).unwrap();
+    assert_eq!(parser.gnu_properties(&data), Default::default());
+}
+
+#[test]
+fn test_overlapping_segments() {
+    let text = Ph::load(PF_R | PF_X, 0, 0, 0x1800, 0x1800);
+    // Shares the partial page at 0x1000 with the text segment.
+    let rw = Ph::load(PF_R | PF_W, 0x1800, 0x1800, 0x100, 0x1000);
+    let elf = common::elf64(ET_DYN, 0, &[text, rw]);
+    let headers = common::headers(&elf);
+    let parser = ELFParser::new(&headers, 0).unwrap();
+    assert_eq!(parser.validate_segments(), Ok(()));
+
+    // Overlaps the last byte of the text segment, and is listed first.
+    let rw = Ph::load(PF_R | PF_W, 0x17ff, 0x17ff, 0x100, 0x1000);
+    let elf = common::elf64(ET_DYN, 0, &[rw, text]);
+    let headers = common::headers(&elf);
+    let parser = ELFParser::new(&headers, 0).unwrap();
+    assert_eq!(
+        parser.validate_segments(),
+        Err(ElfParseError::OverlappingSegments)
+    );
+}
+
+#[test]
+fn test_load_segments_sorted() {
+    let data = common::elf64(
+        ET_DYN,
+        0,
+        &[
+            Ph::load(PF_R | PF_W, 0x3000, 0x3000, 0x100, 0x1800),
+            Ph::new(PT_NOTE, PF_R, 0x200, 0x200, 0x20, 0x20),
+            Ph::load(PF_R, 0, 0, 0x800, 0x800),
+            Ph::load(PF_R | PF_X, 0x1000, 0x1000, 0x1000, 0x1000),
+        ],
+    );
+    let headers = common::headers(&data);
+    let parser = ELFParser::new(&headers, 0x10_0000).unwrap();
+    let vaddrs = parser
+        .load_segments_sorted()
+        .map(|ph| ph.vaddr)
+        .collect::<Vec<_>>();
+    assert_eq!(vaddrs, [0x10_0000, 0x10_1000, 0x10_3000]);
+    assert_eq!(parser.ph_load().next().unwrap().vaddr, 0x10_3000);
+    // The regions are computed in vaddr order as well.
+    let regions = parser
+        .merged_load_regions(0x1000)
+        .map(|region| region.map_vaddr)
+        .collect::<Vec<_>>();
+    assert_eq!(regions, [0x10_0000, 0x10_1000, 0x10_3000]);
+
+    // Segments at the same address keep the order of the table.
+    let data = common::elf64(
+        ET_DYN,
+        0,
+        &[
+            Ph::load(PF_R, 0x2000, 0x1000, 0, 0),
+            Ph::load(PF_R, 0, 0, 0x800, 0x800),
+            Ph::load(PF_R, 0x1000, 0x1000, 0x100, 0x100),
+        ],
+    );
+    let headers = common::headers(&data);
+    let parser = ELFParser::new(&headers, 0).unwrap();
+    let offsets = parser
+        .load_segments_sorted()
+        .map(|ph| ph.offset)
+        .collect::<Vec<_>>();
+    assert_eq!(offsets, [0, 0x2000, 0x1000]);
+
+    // A large table in reverse order, sorted in O(n log n) time with `alloc`.
+    #[cfg(feature = "alloc")]
+    {
+        let phs = (0..2000u64)
+            .rev()
+            .map(|i| Ph::load(PF_R, 0, i * 0x1000, 0, 0x1000))
+            .collect::<Vec<_>>();
+        let data = common::elf64(ET_DYN, 0, &phs);
+        let headers = common::headers(&data);
+        let parser = ELFParser::new(&headers, 0).unwrap();
+        assert!(
+            parser
+                .load_segments_sorted()
+                .map(|ph| ph.vaddr)
+                .eq((0..2000).map(|i| i * 0x1000))
+        );
+    }
+}
+
+#[test]
+fn test_merged_load_regions() {
+    let data = common::elf64(
+        ET_DYN,
+        0,
+        &[
+            Ph::load(PF_R, 0, 0, 0x800, 0x800),
+            Ph::load(PF_R, 0x1000, 0x1000, 0x1000, 0x1000),
+            // Differing flags.
+            Ph::load(PF_R | PF_X, 0x2000, 0x2000, 0x1000, 0x1000),
+            Ph::load(PF_R | PF_W, 0x3000, 0x3000, 0x100, 0x1800),
+            // Follows a region with .bss.
+            Ph::load(PF_R | PF_W, 0x5000, 0x5000, 0x1000, 0x1000),
+        ],
+    );
+    let headers = common::headers(&data);
+    let parser = ELFParser::new(&headers, 0).unwrap();
+    let regions = parser
+        .merged_load_regions(0x1000)
+        .map(|region| (region.map_vaddr, region.map_offset, region.map_size))
+        .collect::<Vec<_>>();
+    assert_eq!(
+        regions,
+        [
+            (0, 0, 0x2000),
+            (0x2000, 0x2000, 0x1000),
+            (0x3000, 0x3000, 0x2000),
+            (0x5000, 0x5000, 0x1000),
+        ]
+    );
+}
+
+#[test]
+fn test_shared_page() {
+    let regions = |phs: &[Ph]| {
+        let data = common::elf64(ET_DYN, 0, phs);
+        let headers = common::headers(&data);
+        let parser = ELFParser::new(&headers, 0).unwrap();
+        parser
+            .merged_load_regions(0x1000)
+            .map(|r| {
+                (
+                    r.map_vaddr,
+                    r.map_offset,
+                    r.map_size,
+                    r.flags,
+                    r.first_page_pad,
+                )
+            })
+            .collect::<Vec<_>>()
+    };
+    let (rx, rw) = (
+        MappingFlags::READ | MappingFlags::EXECUTE | MappingFlags::USER,
+        MappingFlags::READ | MappingFlags::WRITE | MappingFlags::USER,
+    );
+    let rwx = rx | rw;
+
+    // `.text` and `.data` share the page at 0x1000, which gets both the
+    // write and the execute permissions.
+    let text = Ph::load(PF_R | PF_X, 0, 0, 0x1800, 0x1800);
+    let data = Ph::load(PF_R | PF_W, 0x1800, 0x1800, 0x1000, 0x1000);
+    assert_eq!(
+        regions(&[text, data]),
+        [
+            (0, 0, 0x1000, rx, 0),
+            (0x1000, 0x1000, 0x1000, rwx, 0),
+            (0x2000, 0x2000, 0x1000, rw, 0),
+        ]
+    );
+
+    // Both segments are within the shared page.
+    let text = Ph::load(PF_R | PF_X, 0x100, 0x100, 0x100, 0x100);
+    let data = Ph::load(PF_R | PF_W, 0x200, 0x200, 0x100, 0x100);
+    assert_eq!(regions(&[text, data]), [(0, 0, 0x1000, rwx, 0x100)]);
+
+    // Segments with the same flags sharing a page are merged.
+    let first = Ph::load(PF_R, 0, 0, 0x1800, 0x1800);
+    let second = Ph::load(PF_R, 0x1800, 0x1800, 0x1000, 0x1000);
+    assert_eq!(
+        regions(&[first, second]),
+        [(0, 0, 0x3000, MappingFlags::READ | MappingFlags::USER, 0)]
+    );
+
+    // An empty segment starting at a page boundary inside the last page of
+    // the previous one maps nothing.
+    let text = Ph::load(PF_R | PF_X, 0, 0, 0x1800, 0x1800);
+    let data = Ph::load(PF_R | PF_W, 0x1000, 0x1000, 0, 0);
+    assert_eq!(regions(&[text, data]), [(0, 0, 0x2000, rx, 0)]);
+}
+
+#[test]
+fn test_entry_nonzero_first_vaddr() {
+    // A PIE linked at 0x10000: the entry point stays at the same offset in
+    // the text segment once the bias is applied.
+    let data = common::elf64(
+        ET_DYN,
+        0x10100,
+        &[Ph::load(PF_R | PF_X, 0, 0x10000, 0x1000, 0x1000)],
+    );
+    let headers = common::headers(&data);
+    let parser = ELFParser::new(&headers, 0x4000_0000).unwrap();
+    let text = parser.ph_load().next().unwrap();
+    assert_eq!(text.vaddr, 0x4001_0000);
+    assert_eq!(parser.entry(), 0x4001_0100);
+    assert_eq!(parser.entry() - text.vaddr, 0x100);
+}
+
+#[test]
+fn test_bss_zero_plan() {
+    let data = common::elf64(
+        ET_DYN,
+        0,
+        &[
+            // The file data ends in the middle of the page at 0x1000.
+            Ph::load(PF_R | PF_W, 0x800, 0x800, 0x900, 0x3000),
+            // The file data ends on a page boundary.
+            Ph::load(PF_R | PF_W, 0x4800, 0x4800, 0x800, 0x1900),
+            // The BSS ends in the partial page.
+            Ph::load(PF_R | PF_W, 0x8000, 0x8000, 0x100, 0x200),
+            Ph::load(PF_R, 0x9000, 0x9000, 0x100, 0x100),
+        ],
+    );
+    let headers = common::headers(&data);
+    let parser = ELFParser::new(&headers, 0x10_0000).unwrap();
+    let plans = parser
+        .ph_load()
+        .map(|ph| parser.bss_zero_plan(&ph, 0x1000).unwrap())
+        .collect::<Vec<_>>();
+    assert_eq!(
+        plans,
+        [
+            (Some((0x10_1100, 0xf00)), 0x10_2000..0x10_4000),
+            (None, 0x10_5000..0x10_7000),
+            (Some((0x10_8100, 0xf00)), 0x10_9000..0x10_9000),
+            (None, 0x10_a000..0x10_a000),
+        ]
+    );
+
+    // The BSS ends in the last page of the address space.
+    let top = usize::MAX as u64 & !0xfff;
+    let data = common::elf64(ET_DYN, 0, &[Ph::load(PF_R | PF_W, 0, top, 0x100, 0x800)]);
+    let headers = common::headers(&data);
+    let parser = ELFParser::new(&headers, 0).unwrap();
+    let ph = parser.ph_load().next().unwrap();
+    assert_eq!(parser.bss_zero_plan(&ph, 0x1000), None);
+}
+
+#[test]
+fn test_validate_entry() {
+    let validate_entry = |entry| {
+        let data = common::elf64(
+            ET_DYN,
+            entry,
+            &[
+                Ph::load(PF_R, 0, 0, 0x1000, 0x1000),
+                Ph::load(PF_R | PF_X, 0x1000, 0x1000, 0x1000, 0x1000),
+            ],
+        );
+        let headers = common::headers(&data);
+        ELFParser::new(&headers, 0x4000_0000)
+            .unwrap()
+            .validate_entry()
+    };
+    assert_eq!(validate_entry(0x1000), Ok(()));
+    assert_eq!(validate_entry(0x1fff), Ok(()));
+    // In the non-executable segment.
+    assert_eq!(validate_entry(0x800), Err(ElfParseError::BadEntry));
+    // Past the end of all segments.
+    assert_eq!(validate_entry(0x2000), Err(ElfParseError::BadEntry));
+}
+
+#[test]
+fn test_read_at_vaddr() {
+    let mut data = common::elf64(
+        ET_DYN,
+        0,
+        &[
+            Ph::load(PF_R | PF_X, 0, 0, 0x1000, 0x1000),
+            Ph::load(PF_R | PF_W, 0x1000, 0x1000, 0x10, 0x1000),
+        ],
+    );
+    data.resize(0x1010, 0);
+    data[0x1008..0x1010].copy_from_slice(&0x1234u64.to_le_bytes());
+    let headers = common::headers(&data);
+    let base = 0x4000_0000;
+    let parser = ELFParser::new(&headers, base).unwrap();
+    assert_eq!(
+        parser.read_at_vaddr(&data, base + 0x1008, 8),
+        Some(&0x1234u64.to_le_bytes()[..])
+    );
+    // In the zero-filled tail.
+    assert_eq!(parser.read_at_vaddr(&data, base + 0x100c, 8), None);
+    // Across the two segments.
+    assert_eq!(parser.read_at_vaddr(&data, base + 0xff8, 0x10), None);
+    // Below the base.
+    assert_eq!(parser.read_at_vaddr(&data, 0x1008, 8), None);
+
+    // A file offset overflowing past the segment start.
+    let data = common::elf64(
+        ET_DYN,
+        0,
+        &[Ph::load(PF_R, u64::MAX - 0xff, 0, 0x1000, 0x1000)],
+    );
+    let headers = common::headers(&data);
+    let parser = ELFParser::new(&headers, 0).unwrap();
+    assert_eq!(parser.read_at_vaddr(&data, 0x800, 8), None);
+}
+
+#[test]
+fn test_vaddr_of_offset() {
+    let data = common::elf64(
+        ET_DYN,
+        0,
+        &[
+            Ph::load(PF_R | PF_X, 0, 0, 0x1000, 0x1000),
+            // Loaded one page above its offset, with a zero-filled tail.
+            Ph::load(PF_R | PF_W, 0x1000, 0x2000, 0x10, 0x1000),
+        ],
+    );
+    let headers = common::headers(&data);
+    let base = 0x4000_0000;
+    let parser = ELFParser::new(&headers, base).unwrap();
+    assert_eq!(parser.vaddr_of_offset(0x40), Some(base + 0x40));
+    assert_eq!(parser.vaddr_of_offset(0x1008), Some(base + 0x2008));
+    // Past the file-backed part.
+    assert_eq!(parser.vaddr_of_offset(0x1010), None);
+    // Round trip through `read_at_vaddr`.
+    let vaddr = parser.vaddr_of_offset(0x1004).unwrap();
+    let mut file = data.clone();
+    file.resize(0x1010, 0);
+    file[0x1004..0x1008].copy_from_slice(b"ELFx");
+    assert_eq!(parser.read_at_vaddr(&file, vaddr, 4), Some(&b"ELFx"[..]));
+
+    // The file part of the segment is larger than its memory part, which ends
+    // at the last byte of the address space.
+    let data = common::elf64(
+        ET_DYN,
+        0,
+        &[Ph::load(PF_R, 0, 0xffff_ffff_ffff_ffe0, 0x1000, 0x1f)],
+    );
+    let headers = common::headers(&data);
+    let parser = ELFParser::new(&headers, 0).unwrap();
+    assert_eq!(parser.vaddr_of_offset(0x1e), Some(0xffff_ffff_ffff_fffe));
+    assert_eq!(parser.vaddr_of_offset(0x1f), None);
+    assert_eq!(parser.vaddr_of_offset(0x100), None);
+}
+
+#[test]
+fn test_load_bias_for() {
+    // The first segment is linked at an unaligned address above 0.
+    let data = common::elf64(
+        ET_DYN,
+        0x10900,
+        &[
+            Ph::load(PF_R | PF_X, 0x800, 0x10800, 0x800, 0x800),
+            Ph::load(PF_R | PF_W, 0x1000, 0x11000, 0x100, 0x1000),
+        ],
+    );
+    let headers = common::headers(&data);
+    // The result doesn't depend on the bias of the parser.
+    let parser = ELFParser::new(&headers, 0x1234_0000).unwrap();
+    let bias = parser.load_bias_for(0x4000_0000, 0x1000);
+    assert_eq!(bias, 0x3fff_0000);
+
+    let parser = ELFParser::new(&headers, bias).unwrap();
+    assert_eq!(parser.image_range(0x1000), Some(0x4000_0000..0x4000_2000));
+    assert_eq!(parser.entry(), 0x4000_0900);
+}
+
+#[test]
+fn test_page_count() {
+    let text = Ph::load(PF_R | PF_X, 0, 0x1000, 0x1800, 0x1800);
+    let data = common::elf64(ET_DYN, 0, &[text]);
+    let headers = common::headers(&data);
+    let parser = ELFParser::new(&headers, 0).unwrap();
+    assert_eq!(parser.page_count(0x1000), Some(2));
+    assert_eq!(parser.memory_size(), 0x1800);
+
+    // The hole between the segments is counted.
+    let rw = Ph::load(PF_R | PF_W, 0x2000, 0x10000, 0x100, 0x100);
+    let data = common::elf64(ET_DYN, 0, &[text, rw]);
+    let headers = common::headers(&data);
+    let parser = ELFParser::new(&headers, 0).unwrap();
+    assert_eq!(parser.page_count(0x1000), Some(16));
+    assert_eq!(parser.memory_size(), 0xf100);
+}
+
+#[test]
+fn test_initial_brk() {
+    let text = Ph::load(PF_R | PF_X, 0, 0, 0x1000, 0x1000);
+    // Mostly BSS: the file data ends at 0x1100, the memory at 0x6000.
+    let bss = Ph::load(PF_R | PF_W, 0x1000, 0x1000, 0x100, 0x5000);
+    let data = common::elf64(ET_DYN, 0, &[text, bss]);
+    let headers = common::headers(&data);
+    let parser = ELFParser::new(&headers, 0x4000_0000).unwrap();
+    assert_eq!(parser.initial_brk(0x1000), Some(0x4000_6000));
+
+    let data = common::elf64(ET_DYN, 0, &[]);
+    let headers = common::headers(&data);
+    let parser = ELFParser::new(&headers, 0x4000_0000).unwrap();
+    assert_eq!(parser.initial_brk(0x1000), Some(0));
+}
+
+#[test]
+fn test_total_bytes() {
+    let text = Ph::load(PF_R | PF_X, 0, 0x1000, 0x1800, 0x1800);
+    let rw = Ph::load(PF_R | PF_W, 0x2000, 0x10000, 0x100, 0x2000);
+    let data = common::elf64(ET_DYN, 0, &[text, rw]);
+    let headers = common::headers(&data);
+    let parser = ELFParser::new(&headers, 0).unwrap();
+    let segments = parser.ph_load().collect::<Vec<_>>();
+    assert_eq!(parser.total_file_bytes(), 0x1900);
+    assert_eq!(
+        parser.total_file_bytes(),
+        segments[0].filesz + segments[1].filesz
+    );
+    assert_eq!(parser.total_mem_bytes(), 0x3800);
+    assert_eq!(
+        parser.total_mem_bytes(),
+        segments[0].memsz + segments[1].memsz
+    );
+    assert!(parser.total_mem_bytes() < parser.memory_size() as u64);
+}
+
+#[test]
+fn test_validate() {
+    let data = common::elf64(ET_DYN, 0, &[Ph::load(PF_R, 0, 0, 0x1000, 0x1000)]);
+    let validate = |data: &[u8], machine| {
+        let headers = common::headers(data);
+        ELFParser::new(&headers, 0).unwrap().validate(machine)
+    };
+    assert_eq!(validate(&data, None), Ok(()));
+    assert_eq!(validate(&data, Some(Machine::X86_64)), Ok(()));
+    assert_eq!(
+        validate(&data, Some(Machine::AArch64)),
+        Err(ElfParseError::WrongArch)
+    );
+
+    // Already rejected by `ELFParser::new`.
+    let new = |data: &[u8]| ELFParser::new(&common::headers(data), 0).err();
+    let mut bad_ident_version = data.clone();
+    bad_ident_version[6] = 0;
+    assert_eq!(
+        new(&bad_ident_version),
+        Some(ElfParseError::UnsupportedVersion)
+    );
+
+    let mut bad_version = data.clone();
+    bad_version[20..24].copy_from_slice(&0u32.to_le_bytes());
+    assert_eq!(new(&bad_version), Some(ElfParseError::UnsupportedVersion));
+    bad_version[20..24].copy_from_slice(&2u32.to_le_bytes());
+    assert_eq!(new(&bad_version), Some(ElfParseError::UnsupportedVersion));
+
+    // Larger entries are still read by `ELFHeadersBuilder`, but misplace
+    // the fields of all entries but the first.
+    let mut bad_phent = data.clone();
+    bad_phent[54..56].copy_from_slice(&64u16.to_le_bytes());
+    bad_phent.resize(64 + 64, 0);
+    assert_eq!(validate(&bad_phent, None), Err(ElfParseError::BadPhentSize));
+
+    // Nothing to map.
+    let notes_only = common::elf64(
+        ET_DYN,
+        0,
+        &[
+            Ph::new(PT_NOTE, PF_R, 0, 0, 0, 0),
+            Ph::new(PT_NOTE, PF_R, 0, 0, 0, 0),
+        ],
+    );
+    assert_eq!(
+        validate(&notes_only, None),
+        Err(ElfParseError::NoLoadableSegments)
+    );
+
+    // Headers not read by `ELFHeadersBuilder` are checked again.
+    let mut big_endian = data.clone();
+    big_endian[5] = 2;
+    let headers = ELFHeaders {
+        header: xmas_elf::header::parse_header(&big_endian).unwrap(),
+        ph: ProgramHeaders::default(),
+    };
+    assert_eq!(
+        ELFParser::new(&headers, 0).unwrap().validate(None),
+        Err(ElfParseError::WrongEndian)
+    );
+}
+
+#[test]
+fn test_validate_offsets() {
+    let data = common::elf64(
+        ET_DYN,
+        0,
+        &[
+            Ph::load(PF_R | PF_X, 0, 0, 0x1000, 0x1000),
+            Ph::load(PF_R | PF_W, 0x1000, 0x1000, 0x800, 0x1000),
+            // Not copied from the file.
+            Ph::new(PT_TLS, PF_R, 0x10_0000, 0x1800, 0x10, 0x10),
+        ],
+    );
+    let headers = common::headers(&data);
+    let parser = ELFParser::new(&headers, 0).unwrap();
+    assert_eq!(parser.validate_offsets(0x1800), Ok(()));
+    assert_eq!(
+        parser.validate_offsets(0x17ff),
+        Err(ElfParseError::Truncated)
+    );
+
+    // The end of the segment overflows.
+    let data = common::elf64(
+        ET_DYN,
+        0,
+        &[Ph::load(PF_R, u64::MAX - 0x10, 0, 0x100, 0x100)],
+    );
+    let headers = common::headers(&data);
+    let parser = ELFParser::new(&headers, 0).unwrap();
+    assert_eq!(
+        parser.validate_offsets(usize::MAX),
+        Err(ElfParseError::Truncated)
+    );
+}
+
+#[test]
+fn test_address_overflow() {
+    let top = usize::MAX as u64 & !0xfff;
+    let data = common::elf64(ET_DYN, 0, &[Ph::load(PF_R, 0, top, 0x1000, 0x1000)]);
+    let headers = common::headers(&data);
+    assert!(ELFParser::new(&headers, 0).is_err_and(|e| e == ElfParseError::AddressOverflow));
+    assert!(ELFParser::new(&headers, 0x1000).is_err_and(|e| e == ElfParseError::AddressOverflow));
+
+    let data = common::elf64(
+        ET_DYN,
+        0,
+        &[Ph::load(PF_R, 0, top - 0x1000, 0x1000, 0x1000)],
+    );
+    let headers = common::headers(&data);
+    assert!(ELFParser::new(&headers, 0).is_ok());
+    assert!(ELFParser::new(&headers, 0x1000).is_err_and(|e| e == ElfParseError::AddressOverflow));
+
+    // A segment ending in the last page can't be rounded up to a page.
+    let data = common::elf64(ET_DYN, 0, &[Ph::load(PF_R, 0, top, 0x800, 0x800)]);
+    let headers = common::headers(&data);
+    let parser = ELFParser::new(&headers, 0).unwrap();
+    assert_eq!(parser.image_range(0x1000), None);
+    assert_eq!(parser.initial_brk(0x1000), None);
+    assert_eq!(parser.page_count(0x1000), None);
+    assert_eq!(parser.memory_size(), 0x800);
+
+    // The entry point overflows.
+    let data = common::elf64(ET_DYN, top, &[Ph::load(PF_R, 0, 0, 0x1000, 0x1000)]);
+    let headers = common::headers(&data);
+    assert!(ELFParser::new(&headers, 0xfff).is_ok());
+    assert!(ELFParser::new(&headers, 0x1000).is_err_and(|e| e == ElfParseError::AddressOverflow));
+
+    // The program header table is in the file part of a segment, but past
+    // its `p_memsz`, where the address overflows.
+    let data = common::elf64(
+        ET_DYN,
+        0,
+        &[Ph::load(PF_R, 0, 0xffff_ffff_ffff_ffe0, 0x1000, 0x10)],
+    );
+    let headers = common::headers(&data);
+    let parser = ELFParser::new(&headers, 0).unwrap();
+    assert_eq!(parser.phdr(), Err(ElfParseError::AddressOverflow));
+    assert_eq!(parser.phdr_fallback(0x1234), 0x1234);
+    assert!(
+        parser
+            .aux_vector(0x1000, 100, None, None, Credentials::default(), &[])
+            .is_err_and(|e| e == ElfParseError::AddressOverflow)
+    );
+    assert!(
+        parser
+            .aux_builder()
+            .build()
+            .is_err_and(|e| e == ElfParseError::AddressOverflow)
+    );
+}
+
+#[test]
+fn test_shared_library_is_not_pie() {
+    let data = common::elf64(
+        ET_DYN,
+        0,
+        &[
+            Ph::load(PF_R | PF_W, 0, 0, 0x1000, 0x1000),
+            Ph::new(PT_DYNAMIC, PF_R | PF_W, 0x800, 0x800, 0x10, 0x10),
+        ],
+    );
+    let headers = common::headers(&data);
+    let parser = ELFParser::new(&headers, 0).unwrap();
+    assert!(!parser.is_pie());
+    assert!(parser.is_dynamic());
+}
+
+#[test]
+fn test_debug() {
+    let data = common::elf64(
+        ET_DYN,
+        0x1100,
+        &[
+            Ph::load(PF_R | PF_X, 0, 0x1000, 0x800, 0x800),
+            Ph::load(PF_R | PF_W, 0x800, 0x2800, 0x100, 0x200),
+        ],
+    );
+    let headers = common::headers(&data);
+    let parser = ELFParser::new(&headers, 0x4000_0000).unwrap();
+    assert_eq!(
+        format!("{parser:?}"),
+        "ELFParser { base: 0x40000000, entry: 0x40001100, phnum: 2, is_pie: true }"
+    );
+    let segments = parser
+        .ph_load()
+        .map(|ph| format!("{ph:?}"))
+        .collect::<Vec<_>>();
+    assert_eq!(
+        segments,
+        [
+            "ELFPH { kind: 0x1, vaddr: 0x40001000, memsz: 0x800, filesz: 0x800, offset: 0x0, flags: r-x }",
+            "ELFPH { kind: 0x1, vaddr: 0x40002800, memsz: 0x200, filesz: 0x100, offset: 0x800, flags: rw- }",
+        ]
+    );
+}
+
+#[test]
+fn test_perm_str() {
+    let table = [
+        (0, "---"),
+        (PF_X, "--x"),
+        (PF_W, "-w-"),
+        (PF_W | PF_X, "-wx"),
+        (PF_R, "r--"),
+        (PF_R | PF_X, "r-x"),
+        (PF_R | PF_W, "rw-"),
+        (PF_R | PF_W | PF_X, "rwx"),
+    ];
+    let phs = table
+        .iter()
+        .enumerate()
+        .map(|(i, &(flags, _))| {
+            let at = i as u64 * 0x1000;
+            Ph::load(flags, at, at, 0x100, 0x100)
+        })
+        .collect::<Vec<_>>();
+    let data = common::elf64(ET_DYN, 0, &phs);
+    let headers = common::headers(&data);
+    let parser = ELFParser::new(&headers, 0).unwrap();
+    for (ph, (flags, perm)) in parser.ph_load().zip(table) {
+        assert_eq!(ph.perm_str(), perm);
+        assert_eq!(ph.is_read(), flags & PF_R != 0);
+        assert_eq!(ph.is_write(), flags & PF_W != 0);
+        assert_eq!(ph.is_exec(), flags & PF_X != 0);
+    }
+}
+
+#[test]
+fn test_unaligned_offset() {
+    // The offset and the virtual address share the 0x234 sub-page remainder.
+    let data = common::elf64(
+        ET_DYN,
+        0,
+        &[Ph::load(PF_R | PF_W, 0x1234, 0x5234, 0x100, 0x100)],
+    );
+    let headers = common::headers(&data);
+    let parser = ELFParser::new(&headers, 0).unwrap();
+    let ph = parser.ph_load().next().unwrap();
+    assert!(ph.is_congruent());
+    assert_eq!(parser.validate_congruence(), Ok(()));
+    let region = parser.ph_load_aligned(0x1000).next().unwrap();
+    assert_eq!(region.map_vaddr, 0x5000);
+    assert_eq!(region.map_offset, 0x1000);
+    assert_eq!(region.first_page_pad, 0x234);
+    // The byte at `p_offset` in the file lands at `p_vaddr`.
+    assert_eq!(region.map_vaddr + (ph.offset - region.map_offset), ph.vaddr);
+}
+
+#[test]
+fn test_validate_congruence() {
+    let text = Ph::load(PF_R | PF_X, 0, 0, 0x1000, 0x1000);
+    // 0x1100 and 0x2200 differ modulo the page size.
+    let mut rw = Ph::load(PF_R | PF_W, 0x1100, 0x2200, 0x100, 0x100);
+    let data = common::elf64(ET_DYN, 0, &[text, rw]);
+    let headers = common::headers(&data);
+    let parser = ELFParser::new(&headers, 0).unwrap();
+    assert_eq!(
+        parser.validate_congruence(),
+        Err(ElfParseError::MisalignedSegment)
+    );
+
+    // Not constrained without an alignment.
+    rw.align = 1;
+    let data = common::elf64(ET_DYN, 0, &[text, rw]);
+    let headers = common::headers(&data);
+    let parser = ELFParser::new(&headers, 0).unwrap();
+    assert_eq!(parser.validate_congruence(), Ok(()));
+}
+
+#[test]
+fn test_validate_min_addr() {
+    let data = common::elf64(ET_EXEC, 0, &[Ph::load(PF_R | PF_X, 0, 0, 0x1000, 0x1000)]);
+    let headers = common::headers(&data);
+    let parser = ELFParser::new(&headers, 0).unwrap();
+    assert_eq!(
+        parser.validate_min_addr(0x1000),
+        Err(ElfParseError::MapsBelowMin)
+    );
+    assert_eq!(parser.validate_min_addr(0), Ok(()));
+
+    // The bias of a PIE moves it above the limit.
+    let data = common::elf64(ET_DYN, 0, &[Ph::load(PF_R | PF_X, 0, 0, 0x1000, 0x1000)]);
+    let headers = common::headers(&data);
+    let parser = ELFParser::new(&headers, 0x1000).unwrap();
+    assert_eq!(parser.validate_min_addr(0x1000), Ok(()));
+}
+
+#[test]
+fn test_new_with_policy() {
+    let data = common::elf64(
+        ET_EXEC,
+        0x40_1000,
+        &[Ph::load(PF_R | PF_X, 0, 0x40_0000, 0x2000, 0x2000)],
+    );
+    let headers = common::headers(&data);
+    // The bias is ignored by default, as by `new`.
+    let parser = ELFParser::new_with_policy(&headers, 0x1000_0000, false).unwrap();
+    assert_eq!(parser.base(), 0);
+    assert_eq!(parser.entry(), 0x40_1000);
+    assert_eq!(
+        parser.base(),
+        ELFParser::new(&headers, 0x1000_0000).unwrap().base()
+    );
+
+    let parser = ELFParser::new_with_policy(&headers, 0x1000_0000, true).unwrap();
+    assert_eq!(parser.base(), 0x1000_0000);
+    assert_eq!(parser.entry(), 0x1040_1000);
+    assert_eq!(parser.ph_load().next().unwrap().vaddr, 0x1040_0000);
+
+    // Shared objects are biased either way.
+    let data = common::elf64(
+        ET_DYN,
+        0x1000,
+        &[Ph::load(PF_R | PF_X, 0, 0, 0x2000, 0x2000)],
+    );
+    let headers = common::headers(&data);
+    for honor in [false, true] {
+        let parser = ELFParser::new_with_policy(&headers, 0x1000_0000, honor).unwrap();
+        assert_eq!(parser.base(), 0x1000_0000);
+    }
+}
+
+#[test]
+fn test_os_abi() {
+    let mut data = common::elf64(ET_DYN, 0, &[Ph::load(PF_R, 0, 0, 0x1000, 0x1000)]);
+    for (abi, ok) in [(0, true), (3, true), (9, false), (0xff, false)] {
+        data[7] = abi;
+        let headers = common::headers(&data);
+        let parser = ELFParser::new(&headers, 0).unwrap();
+        assert_eq!(parser.os_abi(), abi);
+        assert_eq!(parser.validate_os_abi().is_ok(), ok);
+        assert_eq!(parser.validate(None), Ok(()));
+    }
+}
+
+/// A shared object for `e_machine` with a `DT_RELA` table of a relocation of
+/// type `relative` at 0x800, and a symbol relocation of type 1 at 0x808.
+fn rela_elf(e_machine: u16, relative: u32) -> Vec<u8> {
+    let dynamic = 64 + 56 * 2;
+    let rela = dynamic + 16 * 4;
+    let mut data = common::elf64(
+        ET_DYN,
+        0x1000,
+        &[
+            Ph::load(PF_R | PF_W, 0, 0, 0x1000, 0x1000),
+            Ph::new(PT_DYNAMIC, PF_R | PF_W, dynamic, dynamic, 16 * 4, 16 * 4),
+        ],
+    );
+    data[18..20].copy_from_slice(&e_machine.to_le_bytes());
+    // DT_RELA, DT_RELASZ, DT_RELAENT and DT_NULL
+    for (tag, val) in [(7u64, rela), (8, 48), (9, 24), (0, 0)] {
+        data.extend_from_slice(&tag.to_le_bytes());
+        data.extend_from_slice(&val.to_le_bytes());
+    }
+    for (offset, info, addend) in [
+        (0x800u64, relative as u64, 0x100i64),
+        (0x808, 1 << 32 | 1, 0),
+    ] {
+        data.extend_from_slice(&offset.to_le_bytes());
+        data.extend_from_slice(&info.to_le_bytes());
+        data.extend_from_slice(&addend.to_le_bytes());
+    }
+    data
+}
+
+#[test]
+fn test_relative_by_machine() {
+    // x86_64, aarch64, riscv64 and loongarch64 with their R_*_RELATIVE.
+    for (e_machine, relative) in [(62, 8), (183, 1027), (243, 3), (258, 3)] {
+        let data = rela_elf(e_machine, relative);
+        let headers = common::headers(&data);
+        let parser = ELFParser::new(&headers, 0x4000_0000).unwrap();
+        assert!(parser.has_relocations(&data));
+        let categories = parser
+            .relocations(&data)
+            .unwrap()
+            .map(|reloc| (reloc.kind, reloc.category))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            categories,
+            [(relative, RelocKind::Relative), (1, RelocKind::Other)]
+        );
+        let mut writes = Vec::new();
+        parser
+            .apply_relative(&data, |addr, val| writes.push((addr, val)))
+            .unwrap();
+        assert_eq!(writes, [(0x4000_0800, 0x4000_0100)]);
+    }
+
+    // The RELATIVE type of riscv64 is another type on x86_64.
+    let data = rela_elf(62, 3);
+    let headers = common::headers(&data);
+    let parser = ELFParser::new(&headers, 0).unwrap();
+    let relocs = parser.relocations(&data).unwrap().collect::<Vec<_>>();
+    assert_eq!(relocs[0].category, RelocKind::Other);
+
+    // Unknown relocation types on 32-bit ARM.
+    let data = rela_elf(40, 23);
+    let headers = common::headers(&data);
+    let parser = ELFParser::new(&headers, 0).unwrap();
+    assert!(
+        parser
+            .relocations(&data)
+            .unwrap()
+            .all(|reloc| reloc.category == RelocKind::Other)
+    );
+    assert_eq!(
+        parser.apply_relative(&data, |_, _| {}),
+        Err(ElfParseError::UnsupportedArch)
+    );
+}
+
+#[test]
+fn test_relocation_overflow() {
+    // The address of the first relocation overflows once biased.
+    let mut data = rela_elf(62, 8);
+    let rela = 64 + 56 * 2 + 16 * 4;
+    data[rela..rela + 8].copy_from_slice(&(u64::MAX - 0xff).to_le_bytes());
+    let headers = common::headers(&data);
+    let parser = ELFParser::new(&headers, 0x1000).unwrap();
+    assert!(
+        parser
+            .relocations(&data)
+            .is_err_and(|e| e == ElfParseError::BadDynamic)
+    );
+    assert_eq!(
+        parser.apply_relative(&data, |_, _| {}),
+        Err(ElfParseError::BadDynamic)
+    );
+}
+
+#[test]
+fn test_relr_overflow() {
+    // The file-backed part of the segment runs past its memory size, so a
+    // DT_RELR address in the file can still overflow once biased.
+    let vaddr = (usize::MAX as u64 & !0xfff) - 0x1000;
+    let dynamic = 64 + 56 * 2;
+    let mut data = common::elf64(
+        ET_DYN,
+        vaddr,
+        &[
+            Ph::load(PF_R | PF_W, 0, vaddr, 0x1100, 0x10),
+            Ph::new(
+                PT_DYNAMIC,
+                PF_R | PF_W,
+                dynamic,
+                vaddr + dynamic,
+                16 * 4,
+                16 * 4,
+            ),
+        ],
+    );
+    // DT_RELR, DT_RELRSZ, DT_RELRENT and DT_NULL
+    for (tag, val) in [(36u64, vaddr + 0x200), (35, 8), (37, 8), (0, 0)] {
+        data.extend_from_slice(&tag.to_le_bytes());
+        data.extend_from_slice(&val.to_le_bytes());
+    }
+    data.resize(0x1100, 0);
+    data[0x200..0x208].copy_from_slice(&(vaddr + 0x1008).to_le_bytes());
+    let headers = common::headers(&data);
+    let parser = ELFParser::new(&headers, 0x1000).unwrap();
+    assert!(
+        parser
+            .relocations(&data)
+            .is_err_and(|e| e == ElfParseError::BadDynamic)
+    );
+    // Without the bias, the address fits.
+    let parser = ELFParser::new(&headers, 0).unwrap();
+    let relocs = parser.relocations(&data).unwrap().collect::<Vec<_>>();
+    assert_eq!(relocs.len(), 1);
+    assert_eq!(relocs[0].offset, vaddr as usize + 0x1008);
+}
+
+#[test]
+fn test_has_relocations() {
+    let mut data = rela_elf(62, 8);
+    let headers = common::headers(&data);
+    let parser = ELFParser::new(&headers, 0).unwrap();
+    assert!(parser.has_relocations(&data));
+
+    // A DT_RELA table with a DT_RELASZ of 0.
+    let relasz = 64 + 56 * 2 + 16 + 8;
+    data[relasz..relasz + 8].fill(0);
+    let headers = common::headers(&data);
+    let parser = ELFParser::new(&headers, 0).unwrap();
+    assert!(!parser.has_relocations(&data));
+    assert_eq!(parser.relocations(&data).unwrap().count(), 0);
+
+    // No dynamic section at all.
+    let data = common::elf64(
+        ET_EXEC,
+        0x40_1000,
+        &[Ph::load(PF_R | PF_X, 0, 0x40_0000, 0x1000, 0x1000)],
+    );
+    let headers = common::headers(&data);
+    let parser = ELFParser::new(&headers, 0).unwrap();
+    assert!(!parser.has_relocations(&data));
+}